@@ -0,0 +1,20 @@
+use std::fs;
+use crate::model::categorization::CategoryRule;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CategoryRuleReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_category_rules(path: &str) -> Result<Vec<CategoryRule>, CategoryRuleReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| CategoryRuleReadingError::FileError)?;
+
+    let rules: Vec<CategoryRule> = serde_json::from_str(&file_contents).map_err(|_| CategoryRuleReadingError::JsonParsingError)?;
+
+    Ok(rules)
+}
@@ -0,0 +1,20 @@
+use std::fs;
+use crate::config::Config;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_config(path: &str) -> Result<Config, ConfigReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| ConfigReadingError::FileError)?;
+
+    let config: Config = serde_json::from_str(&file_contents).map_err(|_| ConfigReadingError::JsonParsingError)?;
+
+    Ok(config)
+}
@@ -0,0 +1,20 @@
+use std::fs;
+use crate::model::counterparty_normalization::CounterpartyAlias;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CounterpartyAliasReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_counterparty_aliases(path: &str) -> Result<Vec<CounterpartyAlias>, CounterpartyAliasReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| CounterpartyAliasReadingError::FileError)?;
+
+    let aliases: Vec<CounterpartyAlias> = serde_json::from_str(&file_contents).map_err(|_| CounterpartyAliasReadingError::JsonParsingError)?;
+
+    Ok(aliases)
+}
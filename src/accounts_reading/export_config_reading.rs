@@ -0,0 +1,20 @@
+use std::fs;
+use crate::exporters::ExportConfig;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ExportConfigReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_export_config(path: &str) -> Result<ExportConfig, ExportConfigReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| ExportConfigReadingError::FileError)?;
+
+    let config: ExportConfig = serde_json::from_str(&file_contents).map_err(|_| ExportConfigReadingError::JsonParsingError)?;
+
+    Ok(config)
+}
@@ -1 +1,8 @@
-pub mod merge_rule_reading;
\ No newline at end of file
+pub mod merge_rule_reading;
+pub mod category_rule_reading;
+pub mod config_reading;
+pub mod counterparty_alias_reading;
+pub mod export_config_reading;
+pub mod override_reading;
+pub mod planned_item_reading;
+pub mod statement_scanning;
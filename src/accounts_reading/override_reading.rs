@@ -0,0 +1,20 @@
+use std::fs;
+use crate::model::overrides::TransactionOverride;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum OverrideReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_overrides(path: &str) -> Result<Vec<TransactionOverride>, OverrideReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| OverrideReadingError::FileError)?;
+
+    let overrides: Vec<TransactionOverride> = serde_json::from_str(&file_contents).map_err(|_| OverrideReadingError::JsonParsingError)?;
+
+    Ok(overrides)
+}
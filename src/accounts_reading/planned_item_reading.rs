@@ -0,0 +1,20 @@
+use std::fs;
+use crate::analysis::cash_flow_forecast::PlannedItem;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PlannedItemReadingError {
+    #[error("Error reading file.")]
+    FileError,
+    #[error("Error parsing json.")]
+    JsonParsingError
+}
+
+pub fn read_planned_items(path: &str) -> Result<Vec<PlannedItem>, PlannedItemReadingError> {
+
+    let file_contents = fs::read_to_string(path).map_err(|_| PlannedItemReadingError::FileError)?;
+
+    let items: Vec<PlannedItem> = serde_json::from_str(&file_contents).map_err(|_| PlannedItemReadingError::JsonParsingError)?;
+
+    Ok(items)
+}
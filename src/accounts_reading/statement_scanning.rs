@@ -0,0 +1,322 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use thiserror::Error;
+use zip::ZipArchive;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StatementScanningError {
+    #[error("Invalid file pattern {0}.")]
+    InvalidPattern(String),
+}
+
+/// A file which might contain a statement.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StatementSource {
+    /// Where the file was found, archive entries are named like
+    /// `2024/export.zip/statement.csv`.
+    pub name: String,
+    /// The file to parse, an extracted copy for archive entries.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IgnoreReason {
+    Excluded,
+    NotIncluded,
+    NonUtf8Path,
+    UnreadableArchive,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IgnoredFile {
+    pub name: String,
+    pub reason: IgnoreReason,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ScanResult {
+    pub sources: Vec<StatementSource>,
+    pub ignored: Vec<IgnoredFile>,
+}
+
+/// Finds statements in directories, their subdirectories and the `.zip`
+/// archives within. Patterns are matched against the path relative to the
+/// scanned directory, e.g. `2024/**/*.csv`.
+pub struct StatementScanner {
+    /// Everything is included if no include pattern is given.
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Files which are never statements, e.g. the rules files.
+    ignored_files: Vec<PathBuf>,
+    /// Archive entries are extracted below this directory.
+    extract_dir: PathBuf,
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, StatementScanningError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|_| StatementScanningError::InvalidPattern(pattern.clone()))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|_| StatementScanningError::InvalidPattern(patterns.join(", ")))
+}
+
+fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+/// The files of the directory and all subdirectories, sorted by path.
+fn files_below(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            files.extend(files_below(&path)?);
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+impl StatementScanner {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        ignored_files: Vec<PathBuf>,
+        extract_dir: PathBuf,
+    ) -> Result<StatementScanner, StatementScanningError> {
+        Ok(StatementScanner {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(glob_set(include)?)
+            },
+            exclude: glob_set(exclude)?,
+            ignored_files,
+            extract_dir,
+        })
+    }
+
+    fn ignore_reason(&self, relative_path: &str) -> Option<IgnoreReason> {
+        if self.exclude.is_match(relative_path) {
+            Some(IgnoreReason::Excluded)
+        } else if self
+            .include
+            .as_ref()
+            .is_some_and(|i| !i.is_match(relative_path))
+        {
+            Some(IgnoreReason::NotIncluded)
+        } else {
+            None
+        }
+    }
+
+    pub fn scan(&self, dirs: &[PathBuf]) -> io::Result<ScanResult> {
+        let mut result = ScanResult::default();
+
+        for dir in dirs {
+            for path in files_below(dir)? {
+                if self.ignored_files.contains(&path) {
+                    continue;
+                }
+
+                let name = path.to_string_lossy().to_string();
+                let relative_path = path.strip_prefix(dir).unwrap_or(&path).to_str();
+                let Some(relative_path) = relative_path else {
+                    result.ignored.push(IgnoredFile {
+                        name,
+                        reason: IgnoreReason::NonUtf8Path,
+                    });
+                    continue;
+                };
+
+                if is_archive(&path) {
+                    if self.exclude.is_match(relative_path) {
+                        result.ignored.push(IgnoredFile {
+                            name,
+                            reason: IgnoreReason::Excluded,
+                        });
+                    } else {
+                        self.scan_archive(&path, relative_path, &mut result);
+                    }
+                    continue;
+                }
+
+                match self.ignore_reason(relative_path) {
+                    Some(reason) => result.ignored.push(IgnoredFile { name, reason }),
+                    None => result.sources.push(StatementSource { name, path }),
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Extracts the entries of the archive which are not ignored.
+    fn scan_archive(&self, path: &Path, relative_path: &str, result: &mut ScanResult) {
+        let name = path.to_string_lossy().to_string();
+
+        let extracted = File::open(path)
+            .map_err(zip::result::ZipError::Io)
+            .and_then(ZipArchive::new)
+            .and_then(|mut archive| {
+                let mut extracted = Vec::new();
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i)?;
+                    let Some(entry_path) = entry.enclosed_name().filter(|_| entry.is_file()) else {
+                        continue;
+                    };
+                    let entry_name = entry_path.to_string_lossy().to_string();
+
+                    let reason = self.ignore_reason(&format!("{relative_path}/{entry_name}"));
+                    if let Some(reason) = reason {
+                        extracted.push(Err(IgnoredFile {
+                            name: format!("{name}/{entry_name}"),
+                            reason,
+                        }));
+                        continue;
+                    }
+
+                    let target = self.extract_dir.join(relative_path).join(&entry_path);
+                    fs::create_dir_all(target.parent().unwrap())?;
+                    io::copy(&mut entry, &mut File::create(&target)?)?;
+
+                    extracted.push(Ok(StatementSource {
+                        name: format!("{name}/{entry_name}"),
+                        path: target,
+                    }));
+                }
+                Ok(extracted)
+            });
+
+        match extracted {
+            Ok(extracted) => {
+                for entry in extracted {
+                    match entry {
+                        Ok(source) => result.sources.push(source),
+                        Err(ignored) => result.ignored.push(ignored),
+                    }
+                }
+            }
+            Err(_) => result.ignored.push(IgnoredFile {
+                name,
+                reason: IgnoreReason::UnreadableArchive,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "accounts_statement_scanning_{name}_{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("statements/2024/dkb")).unwrap();
+        fs::write(dir.join("statements/2024/dkb/september.csv"), "a").unwrap();
+        fs::write(dir.join("statements/2024/notes.txt"), "b").unwrap();
+        fs::write(dir.join("statements/rules.json"), "c").unwrap();
+        dir
+    }
+
+    fn names(files: Vec<String>, dir: &Path) -> Vec<String> {
+        let prefix = format!("{}/", dir.to_string_lossy());
+        files.iter().map(|f| f.replace(&prefix, "")).collect()
+    }
+
+    #[test]
+    fn subdirectories_are_scanned_with_patterns() {
+        let dir = test_dir("patterns");
+        let scanner = StatementScanner::new(
+            &["**/*.csv".to_string(), "**/*.txt".to_string()],
+            &["**/notes.*".to_string()],
+            vec![],
+            dir.join("extracted"),
+        )
+        .unwrap();
+
+        let result = scanner.scan(&[dir.join("statements")]).unwrap();
+
+        assert_eq!(
+            names(result.sources.into_iter().map(|s| s.name).collect(), &dir),
+            vec!["statements/2024/dkb/september.csv"]
+        );
+        assert_eq!(
+            result.ignored.iter().map(|i| i.reason).collect::<Vec<_>>(),
+            vec![IgnoreReason::Excluded, IgnoreReason::NotIncluded]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn archive_entries_are_extracted() {
+        let dir = test_dir("archive");
+        let mut zip = ZipWriter::new(File::create(dir.join("statements/export.zip")).unwrap());
+        zip.start_file("october.csv", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"statement").unwrap();
+        zip.start_file("readme.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"readme").unwrap();
+        zip.finish().unwrap();
+        fs::write(dir.join("statements/broken.zip"), "no archive").unwrap();
+
+        let scanner = StatementScanner::new(
+            &["**/*.csv".to_string()],
+            &[],
+            vec![dir.join("statements/rules.json")],
+            dir.join("extracted"),
+        )
+        .unwrap();
+
+        let result = scanner.scan(&[dir.join("statements")]).unwrap();
+
+        assert_eq!(
+            names(
+                result.sources.iter().map(|s| s.name.clone()).collect(),
+                &dir
+            ),
+            vec![
+                "statements/2024/dkb/september.csv",
+                "statements/export.zip/october.csv"
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(&result.sources[1].path).unwrap(),
+            "statement"
+        );
+        assert_eq!(
+            result.ignored.iter().map(|i| i.reason).collect::<Vec<_>>(),
+            vec![
+                IgnoreReason::NotIncluded,
+                IgnoreReason::UnreadableArchive,
+                IgnoreReason::NotIncluded
+            ]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
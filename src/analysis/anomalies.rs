@@ -0,0 +1,362 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::Duration;
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::model::{
+    categorization::{categorize, CategoryRule},
+    monthly_reports::median,
+    AccountRecord,
+};
+
+/// Minimum number of bookings of a counterparty or category before an
+/// amount can be considered unusual.
+const MIN_HISTORY: usize = 5;
+
+/// Days after the first booking during which no counterparty counts as new.
+const LEARNING_PERIOD_DAYS: i64 = 90;
+
+/// Currencies recognized in booking texts, e.g. "12,00 USD".
+const FOREIGN_CURRENCIES: [&str; 12] = [
+    "USD", "GBP", "CHF", "SEK", "NOK", "DKK", "PLN", "CZK", "HUF", "JPY", "CAD", "AUD",
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnomalyConfig {
+    /// Payments to new counterparties are reported from this amount on.
+    pub new_counterparty_threshold: f64,
+    /// Amounts further from the median than this many median absolute
+    /// deviations are unusual.
+    pub unusual_amount_factor: f64,
+    /// Equal charges within this many days are considered duplicates.
+    pub duplicate_days: i64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            new_counterparty_threshold: 100.0,
+            unusual_amount_factor: 5.0,
+            duplicate_days: 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Anomaly {
+    UnusualAmount {
+        record: AccountRecord,
+        typical_amount: f64,
+    },
+    NewCounterparty {
+        record: AccountRecord,
+    },
+    DuplicateCharge {
+        record: AccountRecord,
+        duplicate_of: Box<AccountRecord>,
+    },
+    ForeignCurrency {
+        record: AccountRecord,
+        currency: String,
+    },
+}
+
+impl Anomaly {
+    pub fn record(&self) -> &AccountRecord {
+        match self {
+            Anomaly::UnusualAmount { record, .. }
+            | Anomaly::NewCounterparty { record }
+            | Anomaly::DuplicateCharge { record, .. }
+            | Anomaly::ForeignCurrency { record, .. } => record,
+        }
+    }
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::UnusualAmount {
+                record,
+                typical_amount,
+            } => write!(f, "Unusual amount, typically {typical_amount:.2}: {record}"),
+            Anomaly::NewCounterparty { record } => write!(f, "New counterparty: {record}"),
+            Anomaly::DuplicateCharge {
+                record,
+                duplicate_of,
+            } => write!(
+                f,
+                "Possible duplicate of the booking on {}: {record}",
+                duplicate_of.date
+            ),
+            Anomaly::ForeignCurrency { record, currency } => {
+                write!(f, "Foreign currency {currency}: {record}")
+            }
+        }
+    }
+}
+
+fn counterparty_key(record: &AccountRecord) -> String {
+    record.counterparty().to_lowercase()
+}
+
+/// Finds bookings worth a second look, sorted by date.
+pub fn find_anomalies(
+    records: &[AccountRecord],
+    category_rules: &[CategoryRule],
+    config: &AnomalyConfig,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+
+    anomalies.extend(unusual_amounts(records, category_rules, config));
+    anomalies.extend(new_counterparties(records, config));
+    anomalies.extend(duplicate_charges(records, config));
+    anomalies.extend(foreign_currencies(records));
+
+    anomalies.sort_by_key(|a| a.record().date);
+    anomalies
+}
+
+/// Compares the amounts with the earlier bookings of the counterparty, or
+/// of the category if the counterparty has too few earlier bookings. Later
+/// bookings are left out, so that an anomaly is found the same way when it
+/// is booked as when looking back at it.
+fn unusual_amounts(
+    records: &[AccountRecord],
+    category_rules: &[CategoryRule],
+    config: &AnomalyConfig,
+) -> Vec<Anomaly> {
+    let by_counterparty = records
+        .iter()
+        .into_group_map_by(|r| (counterparty_key(r), r.is_spending()));
+    let by_category = records
+        .iter()
+        .filter_map(|r| categorize(r, category_rules).map(|c| ((c, r.is_spending()), r)))
+        .into_group_map();
+
+    records
+        .iter()
+        .filter_map(|record| {
+            // the amounts booked before the record, if there are enough
+            let earlier = |group: &Vec<&AccountRecord>| {
+                let amounts: Vec<f64> = group
+                    .iter()
+                    .filter(|r| r.date < record.date)
+                    .map(|r| r.amount)
+                    .collect();
+                (amounts.len() >= MIN_HISTORY).then_some(amounts)
+            };
+
+            let key = (counterparty_key(record), record.is_spending());
+            let amounts = match earlier(&by_counterparty[&key]) {
+                Some(amounts) => amounts,
+                None => {
+                    let category = categorize(record, category_rules)?;
+                    earlier(by_category.get(&(category, record.is_spending()))?)?
+                }
+            };
+
+            let typical_amount = median(&amounts);
+            let deviations: Vec<f64> = amounts.iter().map(|a| (a - typical_amount).abs()).collect();
+            let scale = median(&deviations).max(typical_amount.abs() * 0.1);
+
+            if (record.amount - typical_amount).abs() > config.unusual_amount_factor * scale {
+                Some(Anomaly::UnusualAmount {
+                    record: record.clone(),
+                    typical_amount,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reports the first payment to every counterparty after the learning
+/// period, if it is big enough.
+fn new_counterparties(records: &[AccountRecord], config: &AnomalyConfig) -> Vec<Anomaly> {
+    let Some(first_date) = records.iter().map(|r| r.date).min() else {
+        return vec![];
+    };
+    let learning_end = first_date + Duration::days(LEARNING_PERIOD_DAYS);
+
+    let mut first_bookings: HashMap<String, &AccountRecord> = HashMap::new();
+    for record in records.iter().sorted_by_key(|r| r.date) {
+        first_bookings
+            .entry(counterparty_key(record))
+            .or_insert(record);
+    }
+
+    first_bookings
+        .into_values()
+        .filter(|r| r.date > learning_end)
+        .filter(|r| r.is_spending() && r.amount.abs() >= config.new_counterparty_threshold)
+        .map(|r| Anomaly::NewCounterparty { record: r.clone() })
+        .collect()
+}
+
+fn duplicate_charges(records: &[AccountRecord], config: &AnomalyConfig) -> Vec<Anomaly> {
+    records
+        .iter()
+        .filter(|r| r.is_spending())
+        .into_group_map_by(|r| (counterparty_key(r), (r.amount * 100.0).round() as i64))
+        .into_values()
+        .flat_map(|group| {
+            group
+                .into_iter()
+                .sorted_by_key(|r| r.date)
+                .tuple_windows()
+                .filter(|(a, b)| (b.date - a.date).num_days() <= config.duplicate_days)
+                .map(|(a, b)| Anomaly::DuplicateCharge {
+                    record: b.clone(),
+                    duplicate_of: Box::new(a.clone()),
+                })
+                .collect::<Vec<Anomaly>>()
+        })
+        .collect()
+}
+
+fn foreign_currencies(records: &[AccountRecord]) -> Vec<Anomaly> {
+    let currency_amount = Regex::new(&format!(
+        r"\d[.,]\d{{2}} ?(?P<currency>{})\b",
+        FOREIGN_CURRENCIES.join("|")
+    ))
+    .unwrap();
+
+    records
+        .iter()
+        .filter_map(|record| {
+            let currency = record.original_currency.clone().or_else(|| {
+                [
+                    Some(record.booking_text.as_str()),
+                    record.purpose.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .find_map(|text| currency_amount.captures(text))
+                .map(|c| c["currency"].to_string())
+            })?;
+
+            Some(Anomaly::ForeignCurrency {
+                record: record.clone(),
+                currency,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::model::test_util::{new_record_with_other_side, str_date};
+
+    fn kinds(anomalies: &[Anomaly]) -> Vec<(&str, NaiveDate)> {
+        anomalies
+            .iter()
+            .map(|a| {
+                let kind = match a {
+                    Anomaly::UnusualAmount { .. } => "unusual",
+                    Anomaly::NewCounterparty { .. } => "new",
+                    Anomaly::DuplicateCharge { .. } => "duplicate",
+                    Anomaly::ForeignCurrency { .. } => "foreign",
+                };
+                (kind, a.record().date)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unusual_amounts_and_new_counterparties_are_found() {
+        let mut records: Vec<AccountRecord> = (1..=9)
+            .map(|month| {
+                new_record_with_other_side(
+                    -50.0 - month as f64,
+                    &format!("10.{month}.2024"),
+                    "Stadtwerke",
+                )
+            })
+            .collect();
+        records.push(new_record_with_other_side(
+            -480.0,
+            "10.10.2024",
+            "Stadtwerke",
+        ));
+        records.push(new_record_with_other_side(-80.0, "20.1.2024", "Baker"));
+        records.push(new_record_with_other_side(
+            -900.0,
+            "20.6.2024",
+            "Unknown Shop",
+        ));
+        records.push(new_record_with_other_side(-20.0, "21.6.2024", "Small Shop"));
+
+        let anomalies = find_anomalies(&records, &[], &AnomalyConfig::default());
+
+        assert_eq!(
+            kinds(&anomalies),
+            vec![
+                ("new", str_date("20.6.2024")),
+                ("unusual", str_date("10.10.2024"))
+            ]
+        );
+        assert_eq!(
+            anomalies[1],
+            Anomaly::UnusualAmount {
+                record: records[9].clone(),
+                typical_amount: -55.0
+            }
+        );
+    }
+
+    #[test]
+    fn duplicate_and_foreign_currency_charges_are_found() {
+        let records = vec![
+            new_record_with_other_side(-19.99, "1.3.2024", "Streaming"),
+            new_record_with_other_side(-19.99, "3.3.2024", "Streaming"),
+            new_record_with_other_side(-19.99, "1.4.2024", "Streaming"),
+            AccountRecord {
+                original_currency: Some("USD".to_string()),
+                ..new_record_with_other_side(-11.12, "5.3.2024", "")
+            },
+            AccountRecord {
+                purpose: Some("Kartenzahlung 25,00 CHF Kurs 0,95".to_string()),
+                ..new_record_with_other_side(-26.32, "6.3.2024", "Bergbahn")
+            },
+        ];
+
+        let anomalies = find_anomalies(&records, &[], &AnomalyConfig::default());
+
+        assert_eq!(
+            kinds(&anomalies),
+            vec![
+                ("duplicate", str_date("3.3.2024")),
+                ("foreign", str_date("5.3.2024")),
+                ("foreign", str_date("6.3.2024"))
+            ]
+        );
+        assert_eq!(
+            anomalies[2],
+            Anomaly::ForeignCurrency {
+                record: records[4].clone(),
+                currency: "CHF".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn amounts_are_only_compared_with_earlier_bookings() {
+        let mut records = vec![new_record_with_other_side(
+            -480.0,
+            "10.1.2024",
+            "Stadtwerke",
+        )];
+        records.extend((2..=7).map(|month| {
+            new_record_with_other_side(-50.0, &format!("10.{month}.2024"), "Stadtwerke")
+        }));
+
+        let anomalies = find_anomalies(&records, &[], &AnomalyConfig::default());
+
+        assert!(anomalies.is_empty());
+    }
+}
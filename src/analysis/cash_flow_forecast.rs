@@ -0,0 +1,301 @@
+use chrono::{Datelike, NaiveDate};
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::model::{
+    account_history::AccountHistory,
+    monthly_reports::{average, MonthlyReports},
+    record_merging::merge_records,
+    year_month::YearMonth,
+    AccountRecord,
+};
+
+use super::recurring_payments::{find_recurring_payments, RecurringSeries};
+
+/// Number of recent complete months the variable bookings are averaged
+/// over.
+pub const VARIABLE_FLOW_MONTHS: usize = 3;
+
+/// A one-off booking expected in the future, e.g. a car repair.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct PlannedItem {
+    pub description: String,
+    pub account_name: String,
+    pub date: NaiveDate,
+    /// Negative for spendings.
+    pub amount: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ForecastPoint {
+    pub date: NaiveDate,
+    pub balance: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AccountForecast {
+    pub account_name: String,
+    /// The last known balance the forecast starts from.
+    pub start: ForecastPoint,
+    /// The projected balances at the end of every forecast month.
+    pub points: Vec<ForecastPoint>,
+    /// The active series continued by the forecast.
+    pub recurring_series: Vec<RecurringSeries>,
+    /// The average monthly sum of all bookings which do not belong to a
+    /// recurring series.
+    pub variable_flow: f64,
+}
+
+impl AccountForecast {
+    /// The projected month end balances below the threshold.
+    pub fn warnings(&self, threshold: f64) -> Vec<ForecastPoint> {
+        self.points
+            .iter()
+            .filter(|p| p.balance < threshold)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Combines all statements of an account into one history with the most
+/// recent balance. Depots are left out, their value is not driven by
+/// payments.
+fn combine_statements(histories: &[AccountHistory]) -> Vec<AccountHistory> {
+    histories
+        .iter()
+        .filter(|h| !h.account_type.is_depot())
+        .into_group_map_by(|h| h.account_name.clone())
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, statements)| {
+            let latest = statements
+                .iter()
+                .max_by_key(|h| h.current_balance_date)
+                .unwrap();
+
+            AccountHistory {
+                period: statements
+                    .iter()
+                    .filter_map(|h| h.period)
+                    .reduce(|a, b| a.union(&b)),
+                records: merge_records(
+                    statements.iter().map(|h| h.records.clone()).collect(),
+                    vec![],
+                ),
+                ..(*latest).clone()
+            }
+        })
+        .collect()
+}
+
+/// Projects the balance of every account for the given number of months.
+pub fn forecast(
+    histories: &[AccountHistory],
+    months: usize,
+    planned_items: &[PlannedItem],
+) -> Vec<AccountForecast> {
+    combine_statements(histories)
+        .iter()
+        .map(|history| forecast_account(history, months, planned_items))
+        .collect()
+}
+
+/// Continues the active recurring series of the account at their cadence
+/// and adds the average of the other bookings of recent complete months,
+/// pro rata for the remainder of the current month.
+pub fn forecast_account(
+    history: &AccountHistory,
+    months: usize,
+    planned_items: &[PlannedItem],
+) -> AccountForecast {
+    let start = ForecastPoint {
+        date: history.current_balance_date,
+        balance: history.current_balance,
+    };
+
+    let recurring_series: Vec<RecurringSeries> =
+        find_recurring_payments(&history.records, start.date)
+            .into_iter()
+            .filter(|s| !s.stopped)
+            .collect();
+
+    let variable_records: Vec<AccountRecord> = history
+        .records
+        .iter()
+        .filter(|r| !recurring_series.iter().any(|s| s.includes(r)))
+        .cloned()
+        .collect();
+    let mut variable_reports = MonthlyReports::create(variable_records);
+    if let Some(covered_period) = history.covered_period() {
+        variable_reports.exclude_incomplete_months(&[covered_period]);
+    }
+    let recent_flows: Vec<f64> = variable_reports
+        .reports
+        .iter()
+        .rev()
+        .take(VARIABLE_FLOW_MONTHS)
+        .map(|r| r.balance())
+        .collect();
+    let variable_flow = average(&recent_flows);
+
+    let first_month = YearMonth::of(start.date);
+    let mut balance = start.balance;
+    let mut from = start.date;
+    let mut points = Vec::new();
+
+    for month in YearMonth::range(first_month, first_month.plus_months(months as i32)) {
+        let to = month.last_day();
+        if to <= from || points.len() == months {
+            continue;
+        }
+
+        let remaining_share = (to - from).num_days() as f64 / to.day() as f64;
+        balance += variable_flow * remaining_share.min(1.0);
+
+        for series in recurring_series.iter() {
+            balance += recurring_amount(series, from, to, from == start.date);
+        }
+
+        balance += planned_items
+            .iter()
+            .filter(|i| i.account_name == history.account_name)
+            .filter(|i| i.date > from && i.date <= to)
+            .map(|i| i.amount)
+            .sum::<f64>();
+
+        points.push(ForecastPoint { date: to, balance });
+        from = to;
+    }
+
+    AccountForecast {
+        account_name: history.account_name.clone(),
+        start,
+        points,
+        recurring_series,
+        variable_flow,
+    }
+}
+
+/// The sum of the expected bookings of the series after `from` up to and
+/// including `to`. Overdue bookings are expected in the first interval.
+fn recurring_amount(series: &RecurringSeries, from: NaiveDate, to: NaiveDate, first: bool) -> f64 {
+    let mut amount = 0.0;
+    let mut date = series.next_expected_date;
+
+    while date <= to {
+        if date > from || first {
+            amount += series.typical_amount;
+        }
+        date = series.cadence.next_date(date);
+    }
+
+    amount
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::model::test_util::{new_record_with_other_side, str_date};
+    use crate::model::{account_history::StatementPeriod, AccountType};
+
+    fn history() -> AccountHistory {
+        let mut records = vec![];
+        for month in 1..=4 {
+            records.push(new_record_with_other_side(
+                3000.0,
+                &format!("1.{month}.2024"),
+                "Employer",
+            ));
+            records.push(new_record_with_other_side(
+                -1000.0,
+                &format!("3.{month}.2024"),
+                "Landlord",
+            ));
+            records.push(new_record_with_other_side(
+                -1500.0,
+                &format!("15.{month}.2024"),
+                "Edeka",
+            ));
+        }
+        // the first Edeka booking differs too much to form a series
+        records[2].amount = -500.0;
+        records.reverse();
+
+        AccountHistory {
+            account_name: "1018793511".to_string(),
+            account_type: AccountType::DKBAccount,
+            current_balance_date: str_date("30.4.2024"),
+            current_balance: 2000.0,
+            period: Some(StatementPeriod {
+                from: str_date("1.1.2024"),
+                to: str_date("30.4.2024"),
+            }),
+            records,
+            positions: vec![],
+        }
+    }
+
+    #[test]
+    fn recurring_and_variable_bookings_are_continued() {
+        let forecast = forecast_account(&history(), 3, &[]);
+
+        assert_eq!(forecast.recurring_series.len(), 2);
+        assert_relative_eq!(forecast.variable_flow, -1500.0);
+        assert_eq!(
+            forecast.points,
+            vec![
+                ForecastPoint {
+                    date: str_date("31.5.2024"),
+                    balance: 2500.0
+                },
+                ForecastPoint {
+                    date: str_date("30.6.2024"),
+                    balance: 3000.0
+                },
+                ForecastPoint {
+                    date: str_date("31.7.2024"),
+                    balance: 3500.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn planned_items_are_included_and_low_balances_are_reported() {
+        let planned_items = vec![
+            PlannedItem {
+                description: "Car repair".to_string(),
+                account_name: "1018793511".to_string(),
+                date: str_date("10.6.2024"),
+                amount: -4000.0,
+            },
+            PlannedItem {
+                description: "Other account".to_string(),
+                account_name: "DE123".to_string(),
+                date: str_date("10.6.2024"),
+                amount: -4000.0,
+            },
+        ];
+
+        let forecast = forecast(&[history()], 3, &planned_items);
+
+        assert_eq!(forecast.len(), 1);
+        let balances: Vec<f64> = forecast[0].points.iter().map(|p| p.balance).collect();
+        assert_eq!(balances, vec![2500.0, -1000.0, -500.0]);
+        assert_eq!(
+            forecast[0].warnings(0.0),
+            vec![
+                ForecastPoint {
+                    date: str_date("30.6.2024"),
+                    balance: -1000.0
+                },
+                ForecastPoint {
+                    date: str_date("31.7.2024"),
+                    balance: -500.0
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use itertools::Itertools;
+
+use crate::model::{year_month::YearMonth, AccountRecord};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CounterpartySummary {
+    pub counterparty: String,
+    /// Sum of all bookings, negative for spendings.
+    pub total: f64,
+    pub count: usize,
+    pub average_amount: f64,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    /// The total of every month of the report, including months without
+    /// bookings.
+    pub monthly_totals: Vec<(YearMonth, f64)>,
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct CounterpartyReport {
+    pub months: Vec<YearMonth>,
+    /// Sorted by total, biggest spendings first.
+    pub summaries: Vec<CounterpartySummary>,
+}
+
+impl CounterpartyReport {
+    /// Groups the records between `from` and `to` (both inclusive, if
+    /// given) by their counterparty.
+    pub fn create(
+        records: &[AccountRecord],
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> CounterpartyReport {
+        let records: Vec<&AccountRecord> = records
+            .iter()
+            .filter(|r| from.is_none_or(|from| r.date >= from))
+            .filter(|r| to.is_none_or(|to| r.date <= to))
+            .collect();
+
+        let (Some(first_month), Some(last_month)) = (
+            records.iter().map(|r| YearMonth::of(r.date)).min(),
+            records.iter().map(|r| YearMonth::of(r.date)).max(),
+        ) else {
+            return CounterpartyReport::default();
+        };
+
+        let months: Vec<YearMonth> = YearMonth::range(first_month, last_month).collect();
+
+        let mut groups: HashMap<&str, Vec<&AccountRecord>> = HashMap::new();
+        for record in records.iter() {
+            groups
+                .entry(record.counterparty())
+                .or_default()
+                .push(record);
+        }
+
+        let summaries = groups
+            .into_iter()
+            .map(|(counterparty, group)| summarize(counterparty, &group, &months))
+            .sorted_by(|a, b| {
+                a.total
+                    .partial_cmp(&b.total)
+                    .unwrap()
+                    .then(a.counterparty.cmp(&b.counterparty))
+            })
+            .collect();
+
+        CounterpartyReport { months, summaries }
+    }
+}
+
+fn summarize(
+    counterparty: &str,
+    records: &[&AccountRecord],
+    months: &[YearMonth],
+) -> CounterpartySummary {
+    let mut totals_by_month: HashMap<YearMonth, f64> = HashMap::new();
+    for record in records.iter() {
+        *totals_by_month
+            .entry(YearMonth::of(record.date))
+            .or_default() += record.amount;
+    }
+
+    let total: f64 = records.iter().map(|r| r.amount).sum();
+
+    CounterpartySummary {
+        counterparty: counterparty.to_string(),
+        total,
+        count: records.len(),
+        average_amount: total / records.len() as f64,
+        first_date: records.iter().map(|r| r.date).min().unwrap(),
+        last_date: records.iter().map(|r| r.date).max().unwrap(),
+        monthly_totals: months
+            .iter()
+            .map(|m| (*m, *totals_by_month.get(m).unwrap_or(&0.0)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::model::test_util::{self, str_date};
+
+    fn new_record(
+        amount: f64,
+        date: &str,
+        other_side: Option<&str>,
+        booking_text: &str,
+    ) -> AccountRecord {
+        AccountRecord {
+            other_side: other_side.map(|s| s.to_string()),
+            booking_text: booking_text.to_string(),
+            ..test_util::new_record(amount, date)
+        }
+    }
+
+    #[test]
+    fn records_are_grouped_by_counterparty() {
+        let records = vec![
+            new_record(-50.0, "3.1.2024", Some("Edeka"), "Kartenzahlung"),
+            new_record(-30.0, "20.3.2024", Some("Edeka"), "Kartenzahlung"),
+            new_record(-100.0, "5.2.2024", None, "DB Vertrieb GmbH"),
+            new_record(2000.0, "28.2.2024", Some("Employer"), "Gehalt"),
+        ];
+
+        let report = CounterpartyReport::create(&records, None, None);
+
+        assert_eq!(
+            report.months,
+            vec![
+                YearMonth::new(2024, 0),
+                YearMonth::new(2024, 1),
+                YearMonth::new(2024, 2)
+            ]
+        );
+
+        let names: Vec<&str> = report
+            .summaries
+            .iter()
+            .map(|s| s.counterparty.as_str())
+            .collect();
+        assert_eq!(names, vec!["DB Vertrieb GmbH", "Edeka", "Employer"]);
+
+        let edeka = &report.summaries[1];
+        assert_relative_eq!(edeka.total, -80.0);
+        assert_eq!(edeka.count, 2);
+        assert_relative_eq!(edeka.average_amount, -40.0);
+        assert_eq!(edeka.first_date, str_date("3.1.2024"));
+        assert_eq!(edeka.last_date, str_date("20.3.2024"));
+        assert_eq!(
+            edeka.monthly_totals,
+            vec![
+                (YearMonth::new(2024, 0), -50.0),
+                (YearMonth::new(2024, 1), 0.0),
+                (YearMonth::new(2024, 2), -30.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn records_outside_of_the_date_range_are_ignored() {
+        let records = vec![
+            new_record(-50.0, "31.12.2023", Some("Edeka"), ""),
+            new_record(-30.0, "1.1.2024", Some("Edeka"), ""),
+            new_record(-20.0, "31.1.2024", Some("Edeka"), ""),
+            new_record(-10.0, "1.2.2024", Some("Edeka"), ""),
+        ];
+
+        let report = CounterpartyReport::create(
+            &records,
+            Some(str_date("1.1.2024")),
+            Some(str_date("31.1.2024")),
+        );
+
+        assert_eq!(report.months, vec![YearMonth::new(2024, 0)]);
+        assert_eq!(report.summaries.len(), 1);
+        assert_relative_eq!(report.summaries[0].total, -50.0);
+        assert_eq!(report.summaries[0].count, 2);
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::NaiveDate;
+use itertools::Itertools;
+
+use crate::model::{
+    account_history::AccountHistory, balance_validation::balance_at_end_of,
+    monthly_reports::MonthlyReports, portfolio::depot_value_at, year_month::YearMonth,
+};
+
+use super::recurring_payments::RecurringSeries;
+
+/// Number of months the trailing KPIs are computed over.
+pub const TRAILING_MONTHS: usize = 12;
+
+/// Months of fixed costs an emergency fund should cover.
+pub const EMERGENCY_FUND_MONTHS: f64 = 6.0;
+
+/// The figures of a single month the KPIs are computed from. Spendings
+/// and fixed costs are negative.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+struct Figures {
+    earnings: f64,
+    spendings: f64,
+    fixed_costs: f64,
+}
+
+/// Ratios are `None` if they cannot be computed, e.g. the savings rate of
+/// a month without earnings.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Kpis {
+    /// The share of the earnings which has not been spent.
+    pub savings_rate: Option<f64>,
+    /// The number of months the liquid balance lasts with the average
+    /// spendings.
+    pub runway_months: Option<f64>,
+    /// The share of the spendings caused by recurring payments.
+    pub fixed_cost_ratio: Option<f64>,
+    /// The share of the emergency fund, `EMERGENCY_FUND_MONTHS` of fixed
+    /// costs, covered by the liquid balance.
+    pub emergency_fund_coverage: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MonthlyKpis {
+    pub month: YearMonth,
+    pub liquid_balance: f64,
+    /// The liquid balance and the market value of the depots.
+    pub net_worth: f64,
+    pub month_kpis: Kpis,
+    /// Computed over up to `TRAILING_MONTHS` months ending with this month.
+    pub trailing_kpis: Kpis,
+}
+
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    if denominator == 0.0 {
+        None
+    } else {
+        Some(numerator / denominator)
+    }
+}
+
+impl Kpis {
+    fn of(figures: &[Figures], liquid_balance: f64) -> Kpis {
+        let months = figures.len() as f64;
+        let earnings: f64 = figures.iter().map(|f| f.earnings).sum();
+        let spendings: f64 = figures.iter().map(|f| f.spendings).sum();
+        let fixed_costs: f64 = figures.iter().map(|f| f.fixed_costs).sum();
+
+        Kpis {
+            savings_rate: if earnings > 0.0 {
+                Some((earnings + spendings) / earnings)
+            } else {
+                None
+            },
+            runway_months: ratio(liquid_balance, -spendings / months),
+            fixed_cost_ratio: ratio(fixed_costs, spendings),
+            emergency_fund_coverage: ratio(
+                liquid_balance,
+                -fixed_costs / months * EMERGENCY_FUND_MONTHS,
+            ),
+        }
+    }
+}
+
+/// The sum of the balances of all accounts except depots at the end of the
+/// day. Each balance is taken from the most recent statement of the
+/// account covering the day. Without one, the most recent statement before
+/// the day is used, or the first statement for days before all of them.
+pub fn liquid_balance_at(histories: &[AccountHistory], date: NaiveDate) -> f64 {
+    histories
+        .iter()
+        .filter(|h| !h.account_type.is_depot())
+        .into_group_map_by(|h| h.account_name.clone())
+        .into_values()
+        .fold(0.0, |sum, statements| {
+            sum + account_balance_at(&statements, date)
+        })
+}
+
+fn account_balance_at(statements: &[&AccountHistory], date: NaiveDate) -> f64 {
+    let newest_first: Vec<&AccountHistory> = statements
+        .iter()
+        .copied()
+        .sorted_by_key(|h| std::cmp::Reverse(h.current_balance_date))
+        .collect();
+
+    if let Some(balance) = newest_first.iter().find_map(|h| balance_at_end_of(h, date)) {
+        return balance;
+    }
+
+    newest_first
+        .iter()
+        .find(|h| h.current_balance_date < date)
+        .or(newest_first.last())
+        .map_or(0.0, |h| h.balance_at(date, &h.current_anchor()))
+}
+
+/// The liquid balance at the end of the day and the value of the depots
+/// according to their most recent holdings export.
+pub fn net_worth_at(histories: &[AccountHistory], date: NaiveDate) -> f64 {
+    liquid_balance_at(histories, date) + depot_value_at(histories, date)
+}
+
+/// Computes the KPIs of every month of the reports. Spendings belonging to
+/// one of the recurring series count as fixed costs.
+pub fn monthly_kpis(
+    reports: &MonthlyReports,
+    histories: &[AccountHistory],
+    recurring_series: &[RecurringSeries],
+) -> Vec<MonthlyKpis> {
+    let figures: Vec<Figures> = reports
+        .reports
+        .iter()
+        .map(|report| Figures {
+            earnings: report.earnings(),
+            spendings: report.spendings(),
+            fixed_costs: report
+                .records
+                .iter()
+                .filter(|r| r.is_spending())
+                .filter(|r| recurring_series.iter().any(|s| s.includes(r)))
+                .map(|r| r.amount)
+                .sum(),
+        })
+        .collect();
+
+    reports
+        .reports
+        .iter()
+        .enumerate()
+        .map(|(i, report)| {
+            let liquid_balance = liquid_balance_at(histories, report.month.last_day());
+            let trailing_start = (i + 1).saturating_sub(TRAILING_MONTHS);
+
+            MonthlyKpis {
+                month: report.month,
+                liquid_balance,
+                net_worth: net_worth_at(histories, report.month.last_day()),
+                month_kpis: Kpis::of(&figures[i..=i], liquid_balance),
+                trailing_kpis: Kpis::of(&figures[trailing_start..=i], liquid_balance),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+    use crate::analysis::recurring_payments::Cadence;
+    use crate::model::test_util::{new_record_with_other_side, str_date};
+    use crate::model::{portfolio::Position, AccountRecord, AccountType};
+
+    fn rent() -> RecurringSeries {
+        RecurringSeries {
+            counterparty: "Landlord".to_string(),
+            cadence: Cadence::Monthly,
+            typical_amount: -1000.0,
+            occurrences: 2,
+            first_date: str_date("1.1.2024"),
+            last_date: str_date("1.2.2024"),
+            next_expected_date: str_date("1.3.2024"),
+            annualized_amount: -12000.0,
+            price_changes: vec![],
+            stopped: false,
+        }
+    }
+
+    #[test]
+    fn kpis_are_computed_per_month_and_trailing() {
+        let records = vec![
+            new_record_with_other_side(4000.0, "1.1.2024", "Employer"),
+            new_record_with_other_side(-1000.0, "1.1.2024", "Landlord"),
+            new_record_with_other_side(-1000.0, "10.1.2024", "Edeka"),
+            new_record_with_other_side(2000.0, "1.2.2024", "Employer"),
+            new_record_with_other_side(-1000.0, "1.2.2024", "Landlord"),
+            new_record_with_other_side(-3000.0, "10.2.2024", "Car dealer"),
+        ];
+        let history = AccountHistory {
+            account_name: "1018793511".to_string(),
+            account_type: AccountType::DKBAccount,
+            current_balance_date: str_date("29.2.2024"),
+            current_balance: 13000.0,
+            period: None,
+            records: records.clone(),
+            positions: vec![],
+        };
+        let depot = AccountHistory {
+            account_name: "1234567890".to_string(),
+            account_type: AccountType::IngDepot,
+            current_balance_date: str_date("31.1.2024"),
+            current_balance: 5000.0,
+            period: None,
+            records: vec![new_record_with_other_side(
+                4500.0,
+                "15.1.2024",
+                "MSCI World",
+            )],
+            positions: vec![Position {
+                isin: "IE00B4L5Y983".to_string(),
+                name: "MSCI World".to_string(),
+                quantity: 50.0,
+                cost_basis: 4500.0,
+                market_value: 5000.0,
+            }],
+        };
+
+        let kpis = monthly_kpis(
+            &MonthlyReports::create(records),
+            &[history, depot],
+            &[rent()],
+        );
+
+        assert_eq!(kpis.len(), 2);
+
+        let january = &kpis[0];
+        assert_relative_eq!(january.liquid_balance, 15000.0);
+        assert_relative_eq!(january.net_worth, 20000.0);
+        assert_relative_eq!(january.month_kpis.savings_rate.unwrap(), 0.5);
+        assert_relative_eq!(january.month_kpis.runway_months.unwrap(), 7.5);
+        assert_relative_eq!(january.month_kpis.fixed_cost_ratio.unwrap(), 0.5);
+        assert_relative_eq!(january.month_kpis.emergency_fund_coverage.unwrap(), 2.5);
+
+        let february = &kpis[1];
+        assert_relative_eq!(february.month_kpis.savings_rate.unwrap(), -1.0);
+        assert_relative_eq!(february.trailing_kpis.savings_rate.unwrap(), 0.0);
+        assert_relative_eq!(
+            february.trailing_kpis.runway_months.unwrap(),
+            13000.0 / 3000.0
+        );
+        assert_relative_eq!(
+            february.trailing_kpis.fixed_cost_ratio.unwrap(),
+            2000.0 / 6000.0
+        );
+    }
+
+    #[test]
+    fn the_liquid_balance_is_taken_from_the_statement_covering_the_day() {
+        let statement =
+            |balance_date: &str, balance: f64, records: Vec<AccountRecord>| AccountHistory {
+                account_name: "1018793511".to_string(),
+                account_type: AccountType::DKBAccount,
+                current_balance_date: str_date(balance_date),
+                current_balance: balance,
+                period: None,
+                records,
+                positions: vec![],
+            };
+        let histories = [
+            statement(
+                "31.1.2024",
+                1000.0,
+                vec![
+                    new_record_with_other_side(-200.0, "20.1.2024", "Edeka"),
+                    new_record_with_other_side(-300.0, "5.1.2024", "Edeka"),
+                ],
+            ),
+            statement(
+                "31.3.2024",
+                1500.0,
+                vec![new_record_with_other_side(500.0, "1.3.2024", "Employer")],
+            ),
+        ];
+
+        assert_relative_eq!(liquid_balance_at(&histories, str_date("10.1.2024")), 1200.0);
+        assert_relative_eq!(liquid_balance_at(&histories, str_date("15.2.2024")), 1000.0);
+        assert_relative_eq!(liquid_balance_at(&histories, str_date("15.3.2024")), 1500.0);
+        assert_relative_eq!(liquid_balance_at(&histories, str_date("1.12.2023")), 1500.0);
+    }
+
+    #[test]
+    fn ratios_without_base_are_unknown() {
+        let kpis = Kpis::of(&[Figures::default()], 100.0);
+
+        assert_eq!(kpis.savings_rate, None);
+        assert_eq!(kpis.runway_months, None);
+        assert_eq!(kpis.fixed_cost_ratio, None);
+        assert_eq!(kpis.emergency_fund_coverage, None);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod anomalies;
+pub mod cash_flow_forecast;
+pub mod counterparty_report;
+pub mod kpis;
+pub mod recurring_payments;
@@ -0,0 +1,322 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::{Duration, Months, NaiveDate};
+use itertools::Itertools;
+
+use crate::model::{monthly_reports::median, AccountRecord};
+
+/// Minimum number of bookings before a series is considered recurring.
+const MIN_OCCURRENCES: usize = 3;
+
+/// Share of intervals between bookings which must match the cadence.
+const MIN_REGULAR_INTERVAL_SHARE: f64 = 0.75;
+
+/// Maximum relative deviation of a booking from the median amount.
+const MAX_AMOUNT_DEVIATION: f64 = 0.3;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Cadence {
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Cadence {
+    const ALL: [Cadence; 4] = [
+        Cadence::Weekly,
+        Cadence::Monthly,
+        Cadence::Quarterly,
+        Cadence::Yearly,
+    ];
+
+    fn interval_days(&self) -> std::ops::RangeInclusive<i64> {
+        match self {
+            Cadence::Weekly => 5..=9,
+            Cadence::Monthly => 25..=35,
+            Cadence::Quarterly => 80..=100,
+            Cadence::Yearly => 340..=390,
+        }
+    }
+
+    /// Days after the expected date before a series counts as stopped.
+    fn grace_days(&self) -> i64 {
+        match self {
+            Cadence::Weekly => 3,
+            Cadence::Monthly => 10,
+            Cadence::Quarterly => 20,
+            Cadence::Yearly => 30,
+        }
+    }
+
+    fn per_year(&self) -> f64 {
+        match self {
+            Cadence::Weekly => 52.0,
+            Cadence::Monthly => 12.0,
+            Cadence::Quarterly => 4.0,
+            Cadence::Yearly => 1.0,
+        }
+    }
+
+    pub fn next_date(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Cadence::Weekly => date + Duration::days(7),
+            Cadence::Monthly => date + Months::new(1),
+            Cadence::Quarterly => date + Months::new(3),
+            Cadence::Yearly => date + Months::new(12),
+        }
+    }
+}
+
+impl Display for Cadence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Cadence::Weekly => "weekly",
+            Cadence::Monthly => "monthly",
+            Cadence::Quarterly => "quarterly",
+            Cadence::Yearly => "yearly",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PriceChange {
+    pub date: NaiveDate,
+    pub old_amount: f64,
+    pub new_amount: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecurringSeries {
+    pub counterparty: String,
+    pub cadence: Cadence,
+    /// The most recent amount.
+    pub typical_amount: f64,
+    pub occurrences: usize,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    pub next_expected_date: NaiveDate,
+    pub annualized_amount: f64,
+    pub price_changes: Vec<PriceChange>,
+    /// No booking arrived when it was expected.
+    pub stopped: bool,
+}
+
+impl RecurringSeries {
+    /// Whether the record belongs to this series.
+    pub fn includes(&self, record: &AccountRecord) -> bool {
+        record.is_spending() == (self.typical_amount < 0.0)
+            && normalized_counterparty(record.counterparty())
+                == normalized_counterparty(&self.counterparty)
+    }
+
+    pub fn has_price_increase(&self) -> bool {
+        self.price_changes
+            .iter()
+            .any(|c| c.new_amount.abs() > c.old_amount.abs())
+    }
+}
+
+impl Display for RecurringSeries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:.2} {} ({} times since {}), last {}, next {}, {:.2} per year",
+            self.counterparty,
+            self.typical_amount,
+            self.cadence,
+            self.occurrences,
+            self.first_date,
+            self.last_date,
+            self.next_expected_date,
+            self.annualized_amount
+        )?;
+        if self.stopped {
+            write!(f, " [stopped]")?;
+        }
+        for change in self.price_changes.iter() {
+            write!(
+                f,
+                " [price changed from {:.2} to {:.2} on {}]",
+                change.old_amount, change.new_amount, change.date
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn normalized_counterparty(counterparty: &str) -> String {
+    counterparty
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphabetic() || c.is_whitespace())
+        .collect::<String>()
+        .split_whitespace()
+        .join(" ")
+}
+
+/// Finds series of bookings with the same counterparty, a similar amount
+/// and a regular cadence. Series without a booking at the expected date
+/// before `as_of` are marked as stopped.
+pub fn find_recurring_payments(
+    records: &[AccountRecord],
+    as_of: NaiveDate,
+) -> Vec<RecurringSeries> {
+    let mut groups: HashMap<(String, bool), Vec<&AccountRecord>> = HashMap::new();
+    for record in records.iter() {
+        let key = (
+            normalized_counterparty(record.counterparty()),
+            record.is_spending(),
+        );
+        groups.entry(key).or_default().push(record);
+    }
+
+    groups
+        .into_values()
+        .filter_map(|group| detect_series(group, as_of))
+        .sorted_by(|a, b| {
+            a.annualized_amount
+                .partial_cmp(&b.annualized_amount)
+                .unwrap()
+                .then(a.counterparty.cmp(&b.counterparty))
+        })
+        .collect()
+}
+
+fn detect_series(mut group: Vec<&AccountRecord>, as_of: NaiveDate) -> Option<RecurringSeries> {
+    if group.len() < MIN_OCCURRENCES {
+        return None;
+    }
+    group.sort_by_key(|r| r.date);
+
+    let amounts: Vec<f64> = group.iter().map(|r| r.amount).collect();
+    let median_amount = median(&amounts);
+    // zero amounts carry no money and have no relative deviation
+    if median_amount == 0.0 {
+        return None;
+    }
+    if amounts
+        .iter()
+        .any(|a| ((a - median_amount) / median_amount).abs() > MAX_AMOUNT_DEVIATION)
+    {
+        return None;
+    }
+
+    let intervals: Vec<i64> = group
+        .iter()
+        .tuple_windows()
+        .map(|(a, b)| (b.date - a.date).num_days())
+        .collect();
+
+    let cadence = Cadence::ALL.into_iter().find(|c| {
+        let regular = intervals
+            .iter()
+            .filter(|i| c.interval_days().contains(i))
+            .count();
+        regular as f64 / intervals.len() as f64 >= MIN_REGULAR_INTERVAL_SHARE
+    })?;
+
+    let price_changes = group
+        .iter()
+        .tuple_windows()
+        .filter(|(a, b)| (a.amount - b.amount).abs() >= 0.01)
+        .map(|(a, b)| PriceChange {
+            date: b.date,
+            old_amount: a.amount,
+            new_amount: b.amount,
+        })
+        .collect();
+
+    let first = group.first()?;
+    let last = group.last()?;
+    let next_expected_date = cadence.next_date(last.date);
+
+    Some(RecurringSeries {
+        counterparty: last.counterparty().to_string(),
+        cadence,
+        typical_amount: last.amount,
+        occurrences: group.len(),
+        first_date: first.date,
+        last_date: last.date,
+        next_expected_date,
+        annualized_amount: last.amount * cadence.per_year(),
+        price_changes,
+        stopped: as_of > next_expected_date + Duration::days(cadence.grace_days()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_util::{new_record_with_other_side, str_date};
+
+    #[test]
+    fn monthly_subscriptions_are_detected() {
+        let records = vec![
+            new_record_with_other_side(-12.99, "3.1.2024", "Netflix International B.V."),
+            new_record_with_other_side(-12.99, "2.2.2024", "NETFLIX INTERNATIONAL B.V."),
+            new_record_with_other_side(-13.99, "4.3.2024", "Netflix International B.V."),
+            new_record_with_other_side(-13.99, "3.4.2024", "Netflix International B.V."),
+            new_record_with_other_side(-54.20, "10.3.2024", "EDEKA"),
+            new_record_with_other_side(-4.10, "17.3.2024", "EDEKA"),
+            new_record_with_other_side(-80.00, "24.3.2024", "EDEKA"),
+        ];
+
+        let series = find_recurring_payments(&records, str_date("10.4.2024"));
+
+        assert_eq!(series.len(), 1);
+        let netflix = &series[0];
+        assert_eq!(netflix.cadence, Cadence::Monthly);
+        assert_eq!(netflix.occurrences, 4);
+        assert_eq!(netflix.last_date, str_date("3.4.2024"));
+        assert_eq!(netflix.next_expected_date, str_date("3.5.2024"));
+        assert!((netflix.annualized_amount + 13.99 * 12.0).abs() < 1e-9);
+        assert!(!netflix.stopped);
+        assert!(netflix.has_price_increase());
+        assert!(netflix.includes(&records[1]));
+        assert!(!netflix.includes(&records[4]));
+        assert_eq!(
+            netflix.price_changes,
+            vec![PriceChange {
+                date: str_date("4.3.2024"),
+                old_amount: -12.99,
+                new_amount: -13.99
+            }]
+        );
+    }
+
+    #[test]
+    fn series_without_expected_booking_are_marked_as_stopped() {
+        let records = vec![
+            new_record_with_other_side(-120.0, "15.1.2022", "Haftpflicht AG"),
+            new_record_with_other_side(-120.0, "15.1.2023", "Haftpflicht AG"),
+            new_record_with_other_side(-120.0, "16.1.2024", "Haftpflicht AG"),
+            new_record_with_other_side(-9.0, "1.1.2024", "Gym"),
+            new_record_with_other_side(-9.0, "8.1.2024", "Gym"),
+            new_record_with_other_side(-9.0, "15.1.2024", "Gym"),
+        ];
+
+        let series = find_recurring_payments(&records, str_date("1.6.2024"));
+
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].counterparty, "Gym");
+        assert_eq!(series[0].cadence, Cadence::Weekly);
+        assert!(series[0].stopped);
+        assert_eq!(series[1].cadence, Cadence::Yearly);
+        assert!(!series[1].stopped);
+    }
+
+    #[test]
+    fn bookings_without_amount_are_no_series() {
+        let records = vec![
+            new_record_with_other_side(0.0, "1.1.2024", "Card verification"),
+            new_record_with_other_side(0.0, "1.2.2024", "Card verification"),
+            new_record_with_other_side(0.0, "1.3.2024", "Card verification"),
+        ];
+
+        let series = find_recurring_payments(&records, str_date("15.3.2024"));
+
+        assert!(series.is_empty());
+    }
+}
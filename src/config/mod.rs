@@ -0,0 +1,271 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+/// Name of the configuration file inside a data directory.
+pub const CONFIG_FILE_NAME: &str = "accounts_config.json";
+
+/// Path of the user wide configuration below `$XDG_CONFIG_HOME`.
+const GLOBAL_CONFIG_PATH: &str = "accounts/config.json";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// Dates like 31.12.2024.
+    De,
+    /// Dates like 2024-12-31.
+    En,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Text,
+    Csv,
+}
+
+/// The files of a data directory which are not statements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFile {
+    OwnAccountRules,
+    CounterpartyAliases,
+    CategoryRules,
+    ExportConfig,
+    PlannedItems,
+    Overrides,
+}
+
+impl DataFile {
+    pub const ALL: [DataFile; 6] = [
+        DataFile::OwnAccountRules,
+        DataFile::CounterpartyAliases,
+        DataFile::CategoryRules,
+        DataFile::ExportConfig,
+        DataFile::PlannedItems,
+        DataFile::Overrides,
+    ];
+
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            DataFile::OwnAccountRules => "own_account_rules.json",
+            DataFile::CounterpartyAliases => "counterparty_aliases.json",
+            DataFile::CategoryRules => "category_rules.json",
+            DataFile::ExportConfig => "export_config.json",
+            DataFile::PlannedItems => "planned_items.json",
+            DataFile::Overrides => "overrides.json",
+        }
+    }
+}
+
+/// Settings of the command line tool. Unset values fall back to the
+/// defaults, relative paths of data files are resolved against the data
+/// directory, output paths against the working directory.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Config {
+    /// Directories containing statements, the data directory if empty.
+    pub statement_dirs: Vec<String>,
+    /// Glob patterns of the statement files, relative to the statement
+    /// directories. All files are read if empty.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub own_account_rules: Option<String>,
+    pub counterparty_aliases: Option<String>,
+    pub category_rules: Option<String>,
+    pub export_config: Option<String>,
+    pub planned_items: Option<String>,
+    pub overrides: Option<String>,
+    /// Maps `AccountHistory::account_name` to a name shown instead.
+    pub account_nicknames: HashMap<String, String>,
+    pub balance_report_path: Option<String>,
+    pub plot_path: Option<String>,
+    pub journal_path: Option<String>,
+    pub report_format: Option<ReportFormat>,
+    /// Whether reports contain pending bookings, false by default.
+    pub include_pending: Option<bool>,
+    pub locale: Option<Locale>,
+    /// Format of dates given on the command line, see `chrono::format`.
+    /// Defaults to the format of the locale.
+    pub date_format: Option<String>,
+}
+
+/// The user wide configuration file, `$XDG_CONFIG_HOME` defaulting to
+/// `$HOME/.config`.
+pub fn global_config_path(xdg_config_home: Option<&str>, home: Option<&str>) -> Option<PathBuf> {
+    let config_home = match (xdg_config_home, home) {
+        (Some(xdg_config_home), _) if !xdg_config_home.is_empty() => PathBuf::from(xdg_config_home),
+        (_, Some(home)) => Path::new(home).join(".config"),
+        _ => return None,
+    };
+    Some(config_home.join(GLOBAL_CONFIG_PATH))
+}
+
+impl Config {
+    /// Combines two configurations, the values set in `overriding` win.
+    /// Nicknames are combined per account.
+    pub fn merge(self, overriding: Config) -> Config {
+        let mut account_nicknames = self.account_nicknames;
+        account_nicknames.extend(overriding.account_nicknames);
+
+        Config {
+            statement_dirs: if overriding.statement_dirs.is_empty() {
+                self.statement_dirs
+            } else {
+                overriding.statement_dirs
+            },
+            include: if overriding.include.is_empty() {
+                self.include
+            } else {
+                overriding.include
+            },
+            exclude: if overriding.exclude.is_empty() {
+                self.exclude
+            } else {
+                overriding.exclude
+            },
+            own_account_rules: overriding.own_account_rules.or(self.own_account_rules),
+            counterparty_aliases: overriding
+                .counterparty_aliases
+                .or(self.counterparty_aliases),
+            category_rules: overriding.category_rules.or(self.category_rules),
+            export_config: overriding.export_config.or(self.export_config),
+            planned_items: overriding.planned_items.or(self.planned_items),
+            overrides: overriding.overrides.or(self.overrides),
+            account_nicknames,
+            balance_report_path: overriding.balance_report_path.or(self.balance_report_path),
+            plot_path: overriding.plot_path.or(self.plot_path),
+            journal_path: overriding.journal_path.or(self.journal_path),
+            report_format: overriding.report_format.or(self.report_format),
+            include_pending: overriding.include_pending.or(self.include_pending),
+            locale: overriding.locale.or(self.locale),
+            date_format: overriding.date_format.or(self.date_format),
+        }
+    }
+
+    pub fn data_file(&self, dir: &Path, file: DataFile) -> PathBuf {
+        let configured = match file {
+            DataFile::OwnAccountRules => &self.own_account_rules,
+            DataFile::CounterpartyAliases => &self.counterparty_aliases,
+            DataFile::CategoryRules => &self.category_rules,
+            DataFile::ExportConfig => &self.export_config,
+            DataFile::PlannedItems => &self.planned_items,
+            DataFile::Overrides => &self.overrides,
+        };
+        dir.join(configured.as_deref().unwrap_or(file.default_name()))
+    }
+
+    pub fn statement_dirs(&self, dir: &Path) -> Vec<PathBuf> {
+        if self.statement_dirs.is_empty() {
+            vec![dir.to_path_buf()]
+        } else {
+            self.statement_dirs.iter().map(|d| dir.join(d)).collect()
+        }
+    }
+
+    pub fn account_nickname<'a>(&'a self, account_name: &'a str) -> &'a str {
+        self.account_nicknames
+            .get(account_name)
+            .map_or(account_name, |n| n.as_str())
+    }
+
+    /// Whether an account given on the command line, by the name given by
+    /// the bank or by its nickname, is the account shown as `shown_name`.
+    pub fn selects_account(&self, selected: &str, shown_name: &str) -> bool {
+        shown_name == selected || shown_name == self.account_nickname(selected)
+    }
+
+    pub fn date_format(&self) -> &str {
+        match (&self.date_format, self.locale) {
+            (Some(date_format), _) => date_format,
+            (None, Some(Locale::En)) => "%Y-%m-%d",
+            (None, _) => "%d.%m.%Y",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overriding_values_win() {
+        let global = Config {
+            statement_dirs: vec!["statements".to_string()],
+            category_rules: Some("/home/user/categories.json".to_string()),
+            account_nicknames: HashMap::from([
+                ("DE01".to_string(), "Checking".to_string()),
+                ("DE02".to_string(), "Savings".to_string()),
+            ]),
+            locale: Some(Locale::En),
+            ..Config::default()
+        };
+        let local = Config {
+            category_rules: Some("categories.json".to_string()),
+            account_nicknames: HashMap::from([("DE02".to_string(), "Holidays".to_string())]),
+            ..Config::default()
+        };
+
+        let config = global.merge(local);
+
+        assert_eq!(config.statement_dirs, vec!["statements".to_string()]);
+        assert_eq!(config.category_rules, Some("categories.json".to_string()));
+        assert_eq!(config.account_nickname("DE01"), "Checking");
+        assert_eq!(config.account_nickname("DE02"), "Holidays");
+        assert_eq!(config.account_nickname("DE03"), "DE03");
+        assert_eq!(config.date_format(), "%Y-%m-%d");
+    }
+
+    #[test]
+    fn accounts_are_selected_by_name_or_nickname() {
+        let config = Config {
+            account_nicknames: HashMap::from([("DE01".to_string(), "Checking".to_string())]),
+            ..Config::default()
+        };
+
+        assert!(config.selects_account("Checking", "Checking"));
+        assert!(config.selects_account("DE01", "Checking"));
+        assert!(!config.selects_account("DE02", "Checking"));
+        assert!(config.selects_account("DE02", "DE02"));
+    }
+
+    #[test]
+    fn paths_are_resolved_against_the_data_directory() {
+        let config = Config {
+            own_account_rules: Some("rules/own.json".to_string()),
+            category_rules: Some("/etc/categories.json".to_string()),
+            ..Config::default()
+        };
+        let dir = Path::new("data");
+
+        assert_eq!(
+            config.data_file(dir, DataFile::OwnAccountRules),
+            PathBuf::from("data/rules/own.json")
+        );
+        assert_eq!(
+            config.data_file(dir, DataFile::CategoryRules),
+            PathBuf::from("/etc/categories.json")
+        );
+        assert_eq!(
+            config.data_file(dir, DataFile::PlannedItems),
+            PathBuf::from("data/planned_items.json")
+        );
+        assert_eq!(config.statement_dirs(dir), vec![PathBuf::from("data")]);
+        assert_eq!(config.date_format(), "%d.%m.%Y");
+    }
+
+    #[test]
+    fn global_config_is_below_xdg_config_home() {
+        assert_eq!(
+            global_config_path(Some("/xdg"), Some("/home/user")),
+            Some(PathBuf::from("/xdg/accounts/config.json"))
+        );
+        assert_eq!(
+            global_config_path(Some(""), Some("/home/user")),
+            Some(PathBuf::from("/home/user/.config/accounts/config.json"))
+        );
+        assert_eq!(global_config_path(None, None), None);
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use chrono::Duration;
+
+use super::*;
+
+/// Writes journals in the Beancount format.
+pub struct BeancountExporter {}
+
+impl JournalExporter for BeancountExporter {
+    fn export(&self, journal: &Journal) -> String {
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "option \"operating_currency\" \"{}\"",
+            journal.currency
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+
+        for (account, date) in opening_dates(journal) {
+            writeln!(out, "{} open {}", date.format("%Y-%m-%d"), account).unwrap();
+        }
+        writeln!(out).unwrap();
+
+        for t in journal.transactions.iter() {
+            writeln!(
+                out,
+                "{} * \"{}\" \"{}\"",
+                t.date.format("%Y-%m-%d"),
+                quoted_text(&t.payee),
+                quoted_text(&t.narration)
+            )
+            .unwrap();
+            for p in t.postings.iter() {
+                writeln!(
+                    out,
+                    "  {}  {} {}",
+                    beancount_account(&p.account),
+                    format_amount(p.amount),
+                    journal.currency
+                )
+                .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        for a in journal.balance_assertions.iter() {
+            writeln!(
+                out,
+                "{} balance {}  {} {}",
+                assertion_date(a).format("%Y-%m-%d"),
+                beancount_account(&a.account),
+                format_amount(a.amount),
+                journal.currency
+            )
+            .unwrap();
+        }
+
+        out
+    }
+}
+
+/// Beancount checks balances at the beginning of the day, the exported
+/// balances are valid at the end of the day.
+fn assertion_date(assertion: &BalanceAssertion) -> NaiveDate {
+    assertion.date + Duration::days(1)
+}
+
+fn opening_dates(journal: &Journal) -> Vec<(String, NaiveDate)> {
+    let posting_dates = journal.transactions.iter().flat_map(|t| {
+        t.postings
+            .iter()
+            .map(move |p| (beancount_account(&p.account), t.date))
+    });
+    let assertion_dates = journal
+        .balance_assertions
+        .iter()
+        .map(|a| (beancount_account(&a.account), a.date));
+
+    posting_dates
+        .chain(assertion_dates)
+        .into_grouping_map()
+        .min()
+        .into_iter()
+        .sorted()
+        .collect()
+}
+
+/// Beancount account components must start with a capital letter or digit
+/// and may only contain letters, digits and dashes.
+fn beancount_account(account: &str) -> String {
+    account
+        .split(':')
+        .map(|component| {
+            let component: String = component
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+
+            let mut chars = component.chars();
+            match chars.next() {
+                Some(first) if first.is_ascii_alphanumeric() => {
+                    first.to_ascii_uppercase().to_string() + chars.as_str()
+                }
+                _ => "X".to_string() + &component,
+            }
+        })
+        .join(":")
+}
+
+fn quoted_text(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn journal_is_exported_in_beancount_format() {
+        let journal = given_a_journal();
+
+        let output = BeancountExporter {}.export(&journal);
+
+        assert_eq!(output, include_str!("testData/expected.beancount"));
+    }
+
+    #[test]
+    fn account_names_are_made_valid_for_beancount() {
+        assert_eq!(
+            beancount_account("Assets:DKBCreditCard:4930********0595"),
+            "Assets:DKBCreditCard:4930--------0595"
+        );
+        assert_eq!(
+            beancount_account("Expenses:food & drinks"),
+            "Expenses:Food---drinks"
+        );
+        assert_eq!(beancount_account("Expenses:_misc"), "Expenses:X-misc");
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt::Write;
+
+use super::*;
+
+/// Writes journals in the plain text format shared by ledger and hledger.
+pub struct LedgerExporter {}
+
+impl JournalExporter for LedgerExporter {
+    fn export(&self, journal: &Journal) -> String {
+        let width = account_column_width(journal);
+        let mut out = String::new();
+
+        for t in journal.transactions.iter() {
+            writeln!(
+                out,
+                "{} * {}",
+                t.date.format("%Y-%m-%d"),
+                ledger_text(&t.payee)
+            )
+            .unwrap();
+            if t.narration != t.payee {
+                writeln!(out, "    ; {}", t.narration).unwrap();
+            }
+            for p in t.postings.iter() {
+                writeln!(
+                    out,
+                    "    {:<width$}  {:>12} {}",
+                    p.account,
+                    format_amount(p.amount),
+                    journal.currency
+                )
+                .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        for a in journal.balance_assertions.iter() {
+            writeln!(out, "{} * Balance assertion", a.date.format("%Y-%m-%d")).unwrap();
+            writeln!(
+                out,
+                "    {:<width$}  {:>12} {} = {} {}",
+                a.account,
+                format_amount(0.0),
+                journal.currency,
+                format_amount(a.amount),
+                journal.currency
+            )
+            .unwrap();
+            writeln!(out).unwrap();
+        }
+
+        out
+    }
+}
+
+fn account_column_width(journal: &Journal) -> usize {
+    journal
+        .transactions
+        .iter()
+        .flat_map(|t| t.postings.iter().map(|p| p.account.len()))
+        .chain(journal.balance_assertions.iter().map(|a| a.account.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+/// A semicolon would start a comment in a transaction description.
+fn ledger_text(s: &str) -> String {
+    s.replace(';', ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn journal_is_exported_in_ledger_format() {
+        let journal = given_a_journal();
+
+        let output = LedgerExporter {}.export(&journal);
+
+        assert_eq!(output, include_str!("testData/expected.journal"));
+    }
+}
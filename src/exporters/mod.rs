@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::model::{
+    account_history::AccountHistory,
+    categorization::{categorize, CategoryRule},
+    record_merging::MergeRule,
+    AccountRecord,
+};
+
+pub mod beancount_exporter;
+pub mod ledger_exporter;
+
+/// Maximum number of days between the two sides of an internal transfer.
+const TRANSFER_MATCHING_DAYS: i64 = 5;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExportConfig {
+    pub currency: String,
+    /// Maps `AccountHistory::account_name` to a journal account.
+    pub asset_accounts: HashMap<String, String>,
+    /// Maps category names to expense or income accounts.
+    pub category_accounts: HashMap<String, String>,
+    pub default_expense_account: String,
+    pub default_income_account: String,
+    /// Counter account for transfers whose other side was not exported.
+    pub transfer_account: String,
+    /// Counter account for the balances before the first exported booking.
+    pub opening_balance_account: String,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            currency: "EUR".to_string(),
+            asset_accounts: HashMap::new(),
+            category_accounts: HashMap::new(),
+            default_expense_account: "Expenses:Uncategorized".to_string(),
+            default_income_account: "Income:Uncategorized".to_string(),
+            transfer_account: "Assets:Transfers".to_string(),
+            opening_balance_account: "Equity:Opening-Balances".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Posting {
+    pub account: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct JournalTransaction {
+    pub date: NaiveDate,
+    pub payee: String,
+    pub narration: String,
+    pub postings: Vec<Posting>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BalanceAssertion {
+    /// The balance at the end of this day.
+    pub date: NaiveDate,
+    pub account: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Journal {
+    pub currency: String,
+    pub transactions: Vec<JournalTransaction>,
+    pub balance_assertions: Vec<BalanceAssertion>,
+}
+
+pub trait JournalExporter {
+    fn export(&self, journal: &Journal) -> String;
+}
+
+struct AccountRecordWithAccount {
+    account: String,
+    record: AccountRecord,
+}
+
+impl Journal {
+    pub fn create(
+        histories: &[AccountHistory],
+        config: &ExportConfig,
+        category_rules: &[CategoryRule],
+        transfer_rules: &[MergeRule],
+    ) -> Journal {
+        // securities are not booked, their cash side is part of the
+        // bookings of the cash accounts
+        let histories: Vec<&AccountHistory> = histories
+            .iter()
+            .filter(|h| !h.account_type.is_depot())
+            .collect();
+
+        let records = unique_records_by_account(&histories, config);
+
+        let balance_assertions: Vec<BalanceAssertion> = histories
+            .iter()
+            .map(|h| BalanceAssertion {
+                date: h.current_balance_date,
+                account: asset_account(h, config),
+                amount: h.current_balance,
+            })
+            .unique_by(|a| (a.date, a.account.clone()))
+            .sorted_by_key(|a| a.date)
+            .collect();
+
+        let mut transactions = opening_transactions(&records, &balance_assertions, config);
+
+        let (transfers, others): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .partition(|r| transfer_rules.iter().any(|rule| rule.applies(&r.record)));
+
+        transactions.extend(others.iter().map(|r| {
+            let counter_account = counter_account(&r.record, config, category_rules);
+            two_leg_transaction(&r.record, &r.account, &counter_account)
+        }));
+
+        transactions.extend(transfer_transactions(transfers, config));
+        transactions.sort_by_key(|t| t.date);
+
+        Journal {
+            currency: config.currency.clone(),
+            transactions,
+            balance_assertions,
+        }
+    }
+}
+
+fn asset_account(history: &AccountHistory, config: &ExportConfig) -> String {
+    match config.asset_accounts.get(&history.account_name) {
+        Some(account) => account.clone(),
+        None => format!(
+            "Assets:{:?}:{}",
+            history.account_type,
+            history.account_name.replace(' ', "")
+        ),
+    }
+}
+
+fn counter_account(
+    record: &AccountRecord,
+    config: &ExportConfig,
+    category_rules: &[CategoryRule],
+) -> String {
+    let category_account =
+        categorize(record, category_rules).and_then(|c| config.category_accounts.get(c));
+
+    match category_account {
+        Some(account) => account.clone(),
+        None if record.is_earning() => config.default_income_account.clone(),
+        None => config.default_expense_account.clone(),
+    }
+}
+
+/// Collects the records contained in the balances of all histories,
+/// removing duplicates of the same account which occur when exports
+/// overlap. Equal bookings within an export are told apart by their
+/// transaction identifiers, so that they are all kept.
+fn unique_records_by_account(
+    histories: &[&AccountHistory],
+    config: &ExportConfig,
+) -> Vec<AccountRecordWithAccount> {
+    histories
+        .iter()
+        .flat_map(|h| {
+            let account = asset_account(h, config);
+            let mut history = (*h).clone();
+            history.assign_transaction_ids();
+            history
+                .records
+                .into_iter()
+                .filter(|r| r.counts_in_balance())
+                .map(move |record| AccountRecordWithAccount {
+                    account: account.clone(),
+                    record,
+                })
+        })
+        .unique_by(|r| (r.account.clone(), r.record.id.clone()))
+        .collect()
+}
+
+/// Books the balance of every account before its first exported booking
+/// against the opening balance account, so that the balance assertions
+/// hold. The balance is derived from the latest assertion of the account.
+fn opening_transactions(
+    records: &[AccountRecordWithAccount],
+    balance_assertions: &[BalanceAssertion],
+    config: &ExportConfig,
+) -> Vec<JournalTransaction> {
+    balance_assertions
+        .iter()
+        .into_group_map_by(|a| a.account.clone())
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .filter_map(|(account, assertions)| {
+            let anchor = assertions.into_iter().max_by_key(|a| a.date)?;
+            let bookings: Vec<&AccountRecord> = records
+                .iter()
+                .filter(|r| r.account == account)
+                .map(|r| &r.record)
+                .collect();
+
+            let booked = bookings
+                .iter()
+                .filter(|r| r.date <= anchor.date)
+                .fold(0.0, |sum, r| sum + r.amount);
+            let opening_balance = anchor.amount - booked;
+            if opening_balance.abs() < 0.005 {
+                return None;
+            }
+
+            let date = match bookings.iter().map(|r| r.date).min() {
+                Some(first) => first - Duration::days(1),
+                None => anchor.date,
+            };
+            Some(JournalTransaction {
+                date,
+                payee: "Opening balance".to_string(),
+                narration: "Opening balance".to_string(),
+                postings: vec![
+                    Posting {
+                        account: account.clone(),
+                        amount: opening_balance,
+                    },
+                    Posting {
+                        account: config.opening_balance_account.clone(),
+                        amount: -opening_balance,
+                    },
+                ],
+            })
+        })
+        .collect()
+}
+
+/// Combines both sides of a transfer between own accounts into a single
+/// transaction. Transfers whose other side is missing are booked against
+/// the configured transfer account.
+fn transfer_transactions(
+    transfers: Vec<AccountRecordWithAccount>,
+    config: &ExportConfig,
+) -> Vec<JournalTransaction> {
+    let (outgoing, mut incoming): (Vec<_>, Vec<_>) =
+        transfers.into_iter().partition(|t| t.record.is_spending());
+
+    let mut transactions = Vec::new();
+
+    for out in outgoing {
+        let matching_index = incoming
+            .iter()
+            .enumerate()
+            .filter(|(_, inc)| {
+                inc.account != out.account
+                    && (inc.record.amount + out.record.amount).abs() < 0.005
+                    && (inc.record.date - out.record.date).num_days().abs()
+                        <= TRANSFER_MATCHING_DAYS
+            })
+            .min_by_key(|(_, inc)| (inc.record.date - out.record.date).num_days().abs())
+            .map(|(i, _)| i);
+
+        match matching_index {
+            Some(i) => {
+                let inc = incoming.remove(i);
+                transactions.push(two_leg_transaction(&out.record, &out.account, &inc.account));
+            }
+            None => transactions.push(two_leg_transaction(
+                &out.record,
+                &out.account,
+                &config.transfer_account,
+            )),
+        }
+    }
+
+    for inc in incoming {
+        transactions.push(two_leg_transaction(
+            &inc.record,
+            &inc.account,
+            &config.transfer_account,
+        ));
+    }
+
+    transactions
+}
+
+fn two_leg_transaction(
+    record: &AccountRecord,
+    account: &str,
+    counter_account: &str,
+) -> JournalTransaction {
+    let payee = record.counterparty();
+
+    let narration = match record.purpose.as_deref() {
+        Some(purpose) if !purpose.trim().is_empty() => purpose,
+        _ => &record.booking_text,
+    };
+
+    JournalTransaction {
+        date: record.date,
+        payee: single_line(payee),
+        narration: single_line(narration),
+        postings: vec![
+            Posting {
+                account: account.to_string(),
+                amount: record.amount,
+            },
+            Posting {
+                account: counter_account.to_string(),
+                amount: -record.amount,
+            },
+        ],
+    }
+}
+
+fn single_line(s: &str) -> String {
+    s.split_whitespace().join(" ")
+}
+
+fn format_amount(amount: f64) -> String {
+    // avoid printing "-0.00"
+    let amount = if amount.abs() < 0.005 { 0.0 } else { amount };
+    format!("{:.2}", amount)
+}
+
+#[cfg(test)]
+mod test_util {
+    use super::*;
+    pub use crate::model::test_util::str_date;
+    use crate::model::{test_util::new_record_with_other_side, AccountType};
+
+    pub fn new_record(amount: f64, date: &str, other_side: &str, purpose: &str) -> AccountRecord {
+        AccountRecord {
+            booking_text: "Buchung".to_string(),
+            purpose: Some(purpose.to_string()),
+            ..new_record_with_other_side(amount, date, other_side)
+        }
+    }
+
+    pub fn given_histories() -> Vec<AccountHistory> {
+        vec![
+            AccountHistory {
+                account_name: "DE08120300001234567890".to_string(),
+                account_type: AccountType::DKBAccount,
+                current_balance_date: str_date("6.3.2024"),
+                current_balance: 1439.99,
+                period: None,
+                records: vec![
+                    new_record(-500.0, "5.3.2024", "Jane Doe", "Sparen"),
+                    new_record(-60.01, "3.3.2024", "EDEKA.BERGER", "Debitk.63   VISA Debit"),
+                    new_record(2000.0, "1.3.2024", "Company", "LOHN; GEHALT 02/24"),
+                ],
+                positions: vec![],
+            },
+            AccountHistory {
+                account_name: "DE08 5001 0517 5553 6114 73".to_string(),
+                account_type: AccountType::IngExtraAccount,
+                current_balance_date: str_date("6.3.2024"),
+                current_balance: 480.0,
+                period: None,
+                records: vec![
+                    new_record(500.0, "6.3.2024", "John Doe", "Sparen"),
+                    new_record(-20.0, "4.3.2024", "John Doe", "Auszahlung"),
+                ],
+                positions: vec![],
+            },
+        ]
+    }
+
+    pub fn given_config() -> ExportConfig {
+        ExportConfig {
+            asset_accounts: HashMap::from([(
+                "DE08120300001234567890".to_string(),
+                "Assets:DKB:Giro".to_string(),
+            )]),
+            category_accounts: HashMap::from([
+                ("Food".to_string(), "Expenses:Food".to_string()),
+                ("Salary".to_string(), "Income:Salary".to_string()),
+            ]),
+            ..ExportConfig::default()
+        }
+    }
+
+    pub fn given_category_rules() -> Vec<CategoryRule> {
+        vec![
+            CategoryRule {
+                category: "Food".to_string(),
+                other_side_contains: Some("EDEKA".to_string()),
+                booking_text_contains: None,
+                purpose_contains: None,
+            },
+            CategoryRule {
+                category: "Salary".to_string(),
+                other_side_contains: None,
+                booking_text_contains: None,
+                purpose_contains: Some("GEHALT".to_string()),
+            },
+        ]
+    }
+
+    pub fn given_transfer_rules() -> Vec<MergeRule> {
+        vec![
+            MergeRule {
+                other_side_is: Some("Jane Doe".to_string()),
+                booking_text_contains: None,
+            },
+            MergeRule {
+                other_side_is: Some("John Doe".to_string()),
+                booking_text_contains: None,
+            },
+        ]
+    }
+
+    pub fn given_a_journal() -> Journal {
+        Journal::create(
+            &given_histories(),
+            &given_config(),
+            &given_category_rules(),
+            &given_transfer_rules(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::*;
+    use super::*;
+
+    #[test]
+    fn records_are_booked_against_their_category_account() {
+        let journal = given_a_journal();
+
+        let food = journal
+            .transactions
+            .iter()
+            .find(|t| t.payee == "EDEKA.BERGER")
+            .unwrap();
+
+        assert_eq!(
+            food.postings,
+            vec![
+                Posting {
+                    account: "Assets:DKB:Giro".to_string(),
+                    amount: -60.01
+                },
+                Posting {
+                    account: "Expenses:Food".to_string(),
+                    amount: 60.01
+                },
+            ]
+        );
+        assert_eq!(food.narration, "Debitk.63 VISA Debit");
+    }
+
+    #[test]
+    fn both_sides_of_an_internal_transfer_become_one_transaction() {
+        let journal = given_a_journal();
+
+        let transfers: Vec<&JournalTransaction> = journal
+            .transactions
+            .iter()
+            .filter(|t| t.narration == "Sparen")
+            .collect();
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].date, str_date("5.3.2024"));
+        assert_eq!(
+            transfers[0].postings,
+            vec![
+                Posting {
+                    account: "Assets:DKB:Giro".to_string(),
+                    amount: -500.0
+                },
+                Posting {
+                    account: "Assets:IngExtraAccount:DE08500105175553611473".to_string(),
+                    amount: 500.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_transfers_are_booked_against_the_transfer_account() {
+        let journal = given_a_journal();
+
+        let withdrawal = journal
+            .transactions
+            .iter()
+            .find(|t| t.narration == "Auszahlung")
+            .unwrap();
+
+        assert_eq!(withdrawal.postings[1].account, "Assets:Transfers");
+    }
+
+    #[test]
+    fn overlapping_exports_of_the_same_account_are_deduplicated() {
+        let mut histories = given_histories();
+        histories.push(given_histories().remove(0));
+
+        let journal = Journal::create(
+            &histories,
+            &given_config(),
+            &given_category_rules(),
+            &given_transfer_rules(),
+        );
+
+        assert_eq!(journal.transactions, given_a_journal().transactions);
+        assert_eq!(journal.balance_assertions.len(), 2);
+    }
+
+    #[test]
+    fn equal_bookings_within_an_export_are_all_kept() {
+        let mut histories = given_histories();
+        histories[0].records.insert(
+            1,
+            new_record(-60.01, "3.3.2024", "EDEKA.BERGER", "Debitk.63   VISA Debit"),
+        );
+        histories[0].current_balance -= 60.01;
+        histories.push(histories[0].clone());
+
+        let journal = Journal::create(
+            &histories,
+            &given_config(),
+            &given_category_rules(),
+            &given_transfer_rules(),
+        );
+
+        let food = journal
+            .transactions
+            .iter()
+            .filter(|t| t.payee == "EDEKA.BERGER")
+            .count();
+        assert_eq!(food, 2);
+        assert!(journal
+            .transactions
+            .iter()
+            .all(|t| t.payee != "Opening balance"));
+    }
+
+    #[test]
+    fn the_balance_before_the_first_booking_is_opened_against_equity() {
+        let mut histories = given_histories();
+        histories[0].current_balance = 1500.0;
+
+        let journal = Journal::create(
+            &histories,
+            &given_config(),
+            &given_category_rules(),
+            &given_transfer_rules(),
+        );
+
+        let opening = &journal.transactions[0];
+        assert_eq!(opening.date, str_date("29.2.2024"));
+        assert_eq!(opening.postings[0].account, "Assets:DKB:Giro");
+        assert!((opening.postings[0].amount - 60.01).abs() < 0.005);
+        assert_eq!(opening.postings[1].account, "Equity:Opening-Balances");
+
+        let balance: f64 = journal
+            .transactions
+            .iter()
+            .flat_map(|t| t.postings.iter())
+            .filter(|p| p.account == "Assets:DKB:Giro")
+            .map(|p| p.amount)
+            .sum();
+        assert!((balance - 1500.0).abs() < 0.005);
+    }
+}
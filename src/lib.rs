@@ -1,3 +1,7 @@
+pub mod analysis;
 pub mod model;
 pub mod parsers;
-pub mod accounts_reading;
\ No newline at end of file
+pub mod accounts_reading;
+pub mod config;
+pub mod exporters;
+pub mod store;
\ No newline at end of file
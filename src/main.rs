@@ -1,110 +1,1270 @@
 use std::{
     fs::{self},
-    str::FromStr,
+    path::{Path, PathBuf},
 };
 
 use accountslib::{
-    accounts_reading::merge_rule_reading::read_merge_rules,
+    accounts_reading::{
+        category_rule_reading::{read_category_rules, CategoryRuleReadingError},
+        config_reading::{read_config, ConfigReadingError},
+        counterparty_alias_reading::{read_counterparty_aliases, CounterpartyAliasReadingError},
+        export_config_reading::{read_export_config, ExportConfigReadingError},
+        merge_rule_reading::{read_merge_rules, MergeRuleReadingError},
+        override_reading::{read_overrides, OverrideReadingError},
+        planned_item_reading::{read_planned_items, PlannedItemReadingError},
+        statement_scanning::{IgnoreReason, ScanResult, StatementScanner, StatementSource},
+    },
+    analysis::{
+        anomalies::{find_anomalies, Anomaly, AnomalyConfig},
+        cash_flow_forecast::{forecast, PlannedItem},
+        counterparty_report::CounterpartyReport,
+        kpis::{liquid_balance_at, monthly_kpis, net_worth_at, Kpis, TRAILING_MONTHS},
+        recurring_payments::find_recurring_payments,
+    },
+    config::{self, global_config_path, Config, DataFile, CONFIG_FILE_NAME},
+    exporters::{
+        beancount_exporter::BeancountExporter, ledger_exporter::LedgerExporter, Journal,
+        JournalExporter,
+    },
     model::{
-        account_history::AccountHistory,
-        monthly_reports::MonthlyReports,
+        account_history::{covered_periods_by_account, AccountHistory, StatementPeriod},
+        balance_validation::validate,
+        card_settlement::{reconcile_card_settlements, remove_settlements},
+        categorization::CategoryRule,
+        counterparty_normalization::CounterpartyNormalizer,
+        monthly_reports::{MonthlyReports, MonthlyStatistics},
+        overrides::apply_overrides,
+        period_reports::{
+            compare_with_last_year, compare_year_to_date, period_reports, Granularity,
+            PeriodComparison,
+        },
+        portfolio::{depot_value_at, holdings_at, is_securities_trade},
+        record_filter::{RecordFilter, RecordOrder, Sign},
         record_merging::{merge_records, merge_records_from_date},
+        refund_matching::{net_refunds, RefundMatching, REFUND_WINDOW_DAYS},
+        year_month::YearMonth,
         AccountRecord,
     },
-    parsers::{
-        dkb_account_parser::DkbAccountParser, parser_factory::ParserFactory, BankStatementParser,
-        ParserError,
-    },
+    parsers::{parser_factory::ParserFactory, ParserError},
+    store::{TransactionStore, STORE_FILE_NAME},
 };
-use chrono::NaiveDate;
-use clap::{Parser, Subcommand};
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand, ValueEnum};
+use itertools::Itertools;
 use plotters::prelude::*;
+use tempfile::TempDir;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     #[command(subcommand)]
     cmd: Commands,
+    /// Configuration file used instead of the one in the data directory
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// Format of the dates given on the command line, e.g. %Y-%m-%d
+    #[arg(long, global = true)]
+    date_format: Option<String>,
+    /// Only read statement files matching this glob pattern
+    #[arg(long, global = true)]
+    include: Vec<String>,
+    /// Skip statement files matching this glob pattern
+    #[arg(long, global = true)]
+    exclude: Vec<String>,
+    /// Include pending bookings in reports
+    #[arg(long, global = true)]
+    include_pending: bool,
 }
 
 #[derive(Subcommand, Debug, Clone)]
 enum Commands {
-    Plot,
+    Plot {
+        dir_path: String,
+        output_path: Option<String>,
+        #[arg(long)]
+        from_store: bool,
+        /// Extends the balances by a forecast of this many months
+        #[arg(long, default_value_t = 0)]
+        forecast_months: usize,
+    },
     Balance {
         dir_path: String,
         report_path: Option<String>,
         start_date: Option<String>,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Import {
+        dir_path: String,
+        /// Statements to import, `-` reads one from stdin. Defaults to all
+        /// statements of the directory.
+        file_paths: Vec<String>,
+    },
+    Validate {
+        dir_path: String,
+    },
+    /// Lists the statement files found and the parsers reading them
+    Sources {
+        dir_path: String,
+    },
+    Recurring {
+        dir_path: String,
+        #[arg(long)]
+        from_store: bool,
+    },
+    /// Lists the positions of the depots and the net worth
+    Portfolio {
+        dir_path: String,
+        #[arg(long)]
+        from_store: bool,
+    },
+    /// Lists the credit card settlements on the giro accounts and whether
+    /// they match the card bookings
+    Settlements {
+        dir_path: String,
+        #[arg(long)]
+        from_store: bool,
+    },
+    /// Lists refunds with the purchases they reverse and refunds without
+    /// purchase
+    Refunds {
+        dir_path: String,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Counterparties {
+        dir_path: String,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        /// Account name given by the bank or its nickname
+        #[arg(long)]
+        account: Option<String>,
+        /// Defaults to the configured report format or text
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Search {
+        dir_path: String,
+        /// Regular expression matched against other side, booking text and purpose
+        text: Option<String>,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(long)]
+        min_amount: Option<f64>,
+        #[arg(long)]
+        max_amount: Option<f64>,
+        #[arg(long, value_enum)]
+        sign: Option<SignArg>,
+        /// Account name given by the bank or its nickname
+        #[arg(long)]
+        account: Option<String>,
+        #[arg(long)]
+        category: Option<String>,
+        /// Only show records with this tag from the overrides
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long, value_enum, default_value = "date")]
+        sort: SortArg,
+        #[arg(long)]
+        descending: bool,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Periods {
+        dir_path: String,
+        #[arg(long, value_enum, default_value = "month")]
+        by: GranularityArg,
+        /// First month (1-12) of the fiscal year, used with `--by fiscal-year`
+        #[arg(long, default_value_t = 1)]
+        fiscal_year_start: u32,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Compare {
+        dir_path: String,
+        /// The month to compare, e.g. 2024-09. Defaults to the latest month.
+        #[arg(long)]
+        month: Option<YearMonth>,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Kpi {
+        dir_path: String,
+        /// Number of most recent months to show
+        #[arg(long, default_value_t = 12)]
+        months: usize,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Forecast {
+        dir_path: String,
+        #[arg(long, default_value_t = 6)]
+        months: usize,
+        /// Warn about projected balances below this amount
+        #[arg(long, default_value_t = 0.0)]
+        threshold: f64,
+        #[arg(long)]
+        from_store: bool,
     },
+    Anomalies {
+        dir_path: String,
+        /// Only show anomalies from this date on
+        #[arg(long)]
+        from: Option<String>,
+        /// Minimum amount of the first payment to a new counterparty
+        #[arg(long, default_value_t = 100.0)]
+        new_counterparty_threshold: f64,
+        #[arg(long)]
+        from_store: bool,
+    },
+    Export {
+        dir_path: String,
+        #[arg(value_enum)]
+        format: ExportFormat,
+        output_path: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ReportFormat {
+    Text,
+    Csv,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SignArg {
+    Earning,
+    Spending,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum SortArg {
+    Date,
+    Amount,
+    Counterparty,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum GranularityArg {
+    Month,
+    Quarter,
+    Year,
+    FiscalYear,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ExportFormat {
+    Ledger,
+    Beancount,
 }
 
 fn main() {
     let args = Args::parse();
 
+    let overrides = Config {
+        date_format: args.date_format,
+        include: args.include,
+        exclude: args.exclude,
+        include_pending: args.include_pending.then_some(true),
+        ..Config::default()
+    };
+    let open = |dir_path: String| DataDir::open(&dir_path, args.config.as_deref(), &overrides);
+
     match args.cmd {
-        Commands::Plot => plot_accounts(),
+        Commands::Plot {
+            dir_path,
+            output_path,
+            from_store,
+            forecast_months,
+        } => {
+            let data = open(dir_path);
+            let output_path = output_path
+                .or(data.config.plot_path.clone())
+                .unwrap_or("./balance.png".to_string());
+            plot_accounts(&data, &output_path, from_store, forecast_months)
+        }
         Commands::Balance {
             dir_path,
             report_path,
             start_date,
-        } => generate_balance_sheet(
-            &dir_path,
-            &report_path.unwrap_or("./balance".to_string()),
-            start_date,
+            from_store,
+        } => {
+            let data = open(dir_path);
+            let report_path = report_path
+                .or(data.config.balance_report_path.clone())
+                .unwrap_or("./balance".to_string());
+            generate_balance_sheet(&data, &report_path, start_date, from_store)
+        }
+        Commands::Import {
+            dir_path,
+            file_paths,
+        } => import_statements(&open(dir_path), file_paths),
+        Commands::Validate { dir_path } => validate_statements(&open(dir_path)),
+        Commands::Sources { dir_path } => list_sources(&open(dir_path)),
+        Commands::Recurring {
+            dir_path,
+            from_store,
+        } => list_recurring_payments(&open(dir_path), from_store),
+        Commands::Counterparties {
+            dir_path,
+            from,
+            to,
+            account,
+            format,
+            from_store,
+        } => list_counterparties(&open(dir_path), from, to, account, format, from_store),
+        Commands::Search {
+            dir_path,
+            text,
+            from,
+            to,
+            min_amount,
+            max_amount,
+            sign,
+            account,
+            category,
+            tag,
+            sort,
+            descending,
+            from_store,
+        } => {
+            let data = open(dir_path);
+            let filter = RecordFilter {
+                from: from.as_deref().map(|d| data.parse_date(d)),
+                to: to.as_deref().map(|d| data.parse_date(d)),
+                min_amount,
+                max_amount,
+                sign: sign.map(|s| match s {
+                    SignArg::Earning => Sign::Earning,
+                    SignArg::Spending => Sign::Spending,
+                }),
+                // the records are named by the nicknames of their accounts
+                account_name: account.map(|a| data.config.account_nickname(&a).to_string()),
+                text: None,
+                category,
+                tag,
+            };
+            let filter = match text {
+                Some(text) => filter.with_text(&text).expect("Invalid search text"),
+                None => filter,
+            };
+            let order = match sort {
+                SortArg::Date => RecordOrder::Date,
+                SortArg::Amount => RecordOrder::Amount,
+                SortArg::Counterparty => RecordOrder::Counterparty,
+            };
+            search_records(&data, &filter, order, descending, from_store)
+        }
+        Commands::Periods {
+            dir_path,
+            by,
+            fiscal_year_start,
+            from_store,
+        } => {
+            let granularity = match by {
+                GranularityArg::Month => Granularity::Month,
+                GranularityArg::Quarter => Granularity::Quarter,
+                GranularityArg::Year => Granularity::Year,
+                GranularityArg::FiscalYear => Granularity::FiscalYear {
+                    first_month0: fiscal_year_start.clamp(1, 12) - 1,
+                },
+            };
+            list_periods(&open(dir_path), granularity, from_store)
+        }
+        Commands::Compare {
+            dir_path,
+            month,
+            from_store,
+        } => compare_with_previous_year(&open(dir_path), month, from_store),
+        Commands::Kpi {
+            dir_path,
+            months,
+            from_store,
+        } => show_kpis(&open(dir_path), months, from_store),
+        Commands::Forecast {
+            dir_path,
+            months,
+            threshold,
+            from_store,
+        } => forecast_balances(&open(dir_path), months, threshold, from_store),
+        Commands::Anomalies {
+            dir_path,
+            from,
+            new_counterparty_threshold,
+            from_store,
+        } => list_anomalies(
+            &open(dir_path),
+            from,
+            new_counterparty_threshold,
+            from_store,
         ),
+        Commands::Export {
+            dir_path,
+            format,
+            output_path,
+        } => export_journal(&open(dir_path), format, output_path),
+        Commands::Portfolio {
+            dir_path,
+            from_store,
+        } => show_portfolio(&open(dir_path), from_store),
+        Commands::Settlements {
+            dir_path,
+            from_store,
+        } => list_card_settlements(&open(dir_path), from_store),
+        Commands::Refunds {
+            dir_path,
+            from_store,
+        } => list_refunds(&open(dir_path), from_store),
     }
 }
 
-fn generate_balance_sheet(dir_path: &str, report_path: &str, start_date: Option<String>) {
-    let dir_entries = fs::read_dir(dir_path).expect("Could not list files in dir {dir_path}");
+/// A data directory together with its configuration.
+struct DataDir {
+    path: PathBuf,
+    config: Config,
+}
+
+impl DataDir {
+    /// Reads the user wide configuration and overrides it with the one of
+    /// the data directory or the given configuration file. Command line
+    /// flags override both.
+    fn open(dir_path: &str, config_path: Option<&str>, overrides: &Config) -> DataDir {
+        let path = PathBuf::from(dir_path);
 
-    let own_account_rules_file = "own_account_rules.json";
+        let global_path = global_config_path(
+            std::env::var("XDG_CONFIG_HOME").ok().as_deref(),
+            std::env::var("HOME").ok().as_deref(),
+        );
+        let global_config = global_path
+            .map(|p| read_optional_file(&p, read_config, ConfigReadingError::FileError))
+            .unwrap_or_default();
 
-    let file_paths: Vec<String> = dir_entries
+        let local_config = match config_path {
+            Some(config_path) => read_config(config_path).expect("Could not read config file"),
+            None => read_optional_file(
+                &path.join(CONFIG_FILE_NAME),
+                read_config,
+                ConfigReadingError::FileError,
+            ),
+        };
+
+        let config = global_config.merge(local_config).merge(overrides.clone());
+
+        DataDir { path, config }
+    }
+
+    fn file(&self, file: DataFile) -> PathBuf {
+        self.config.data_file(&self.path, file)
+    }
+
+    /// Paths of the files which are not statements.
+    fn non_statement_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = DataFile::ALL.iter().map(|f| self.file(*f)).collect();
+        files.push(self.path.join(CONFIG_FILE_NAME));
+        files.push(self.path.join(STORE_FILE_NAME));
+        files
+    }
+
+    fn parse_date(&self, date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, self.config.date_format()).expect("Invalid date")
+    }
+}
+
+/// Reads an optional file, a missing file results in the default value.
+fn read_optional_file<T: Default, E: PartialEq + std::fmt::Display>(
+    path: &Path,
+    read: impl Fn(&str) -> Result<T, E>,
+    file_error: E,
+) -> T {
+    match read(path.to_str().unwrap()) {
+        Ok(value) => value,
+        Err(e) if e == file_error => T::default(),
+        Err(e) => panic!("Could not read {}: {e}", path.display()),
+    }
+}
+
+/// Scans the statement directories, archives are extracted to the given
+/// directory. It is removed when dropped, so it has to be kept until the
+/// statements are parsed.
+fn scan_statements(data: &DataDir, extract_dir: &TempDir) -> ScanResult {
+    let scanner = StatementScanner::new(
+        &data.config.include,
+        &data.config.exclude,
+        data.non_statement_files(),
+        extract_dir.path().to_path_buf(),
+    )
+    .expect("Invalid file pattern");
+
+    scanner
+        .scan(&data.config.statement_dirs(&data.path))
+        .expect("Could not list statement files")
+}
+
+fn archive_extract_dir() -> TempDir {
+    TempDir::new().expect("Could not create a temporary directory")
+}
+
+/// A source with the path `-` is read from stdin.
+fn parse_statement(source: &StatementSource) -> Result<AccountHistory, ParserError> {
+    if source.path.as_os_str() == "-" {
+        return ParserFactory::parse_reader(std::io::stdin().lock());
+    }
+    let file = fs::File::open(&source.path).map_err(|_| ParserError::FileReadError)?;
+    ParserFactory::parse_reader(file)
+}
+
+fn read_account_histories(data: &DataDir) -> Vec<AccountHistory> {
+    let extract_dir = archive_extract_dir();
+    scan_statements(data, &extract_dir)
+        .sources
+        .iter()
+        .map(parse_statement)
+        .filter_map(|r| r.ok())
+        .collect()
+}
+
+/// Reads the histories from the statements or the store, identifies their
+/// records and shows the accounts by their nicknames.
+fn load_account_histories(data: &DataDir, from_store: bool) -> Vec<AccountHistory> {
+    let account_histories = if from_store {
+        TransactionStore::load(&data.path.join(STORE_FILE_NAME))
+            .expect("Could not read transaction store")
+            .account_histories()
+    } else {
+        read_account_histories(data)
+    };
+
+    account_histories
+        .into_iter()
+        .map(|mut h| {
+            // the identifiers use the account name given by the bank, so
+            // that they stay the same when a nickname is changed
+            h.assign_transaction_ids();
+            let mut h = AccountHistory {
+                account_name: data.config.account_nickname(&h.account_name).to_string(),
+                ..h
+            };
+            h.name_records();
+            h
+        })
+        .collect()
+}
+
+fn import_statements(data: &DataDir, file_paths: Vec<String>) {
+    let store_path = data.path.join(STORE_FILE_NAME);
+    let mut store = TransactionStore::load(&store_path).expect("Could not read transaction store");
+
+    let extract_dir = archive_extract_dir();
+    let sources = if file_paths.is_empty() {
+        scan_statements(data, &extract_dir).sources
+    } else {
+        file_paths
+            .into_iter()
+            .map(|path| StatementSource {
+                name: if path == "-" {
+                    "stdin".to_string()
+                } else {
+                    path.clone()
+                },
+                path: PathBuf::from(path),
+            })
+            .collect()
+    };
+
+    for source in sources.iter() {
+        let name = &source.name;
+        match parse_statement(source) {
+            Ok(history) => {
+                let account_name = data
+                    .config
+                    .account_nickname(&history.account_name)
+                    .to_string();
+                let new_records = store.import(history, name, Local::now().naive_local());
+                println!("{name}: {new_records} new records for account {account_name}");
+            }
+            Err(_) => println!("{name}: ignored"),
+        }
+    }
+
+    store
+        .save(&store_path)
+        .expect("Could not write transaction store");
+}
+
+fn list_sources(data: &DataDir) {
+    let extract_dir = archive_extract_dir();
+    let scan_result = scan_statements(data, &extract_dir);
+
+    for source in scan_result.sources.iter() {
+        let path = source.path.to_string_lossy();
+        match ParserFactory::create(&path) {
+            Ok(parser) => match parser.parse(&path) {
+                Ok(history) => println!(
+                    "{}: {} statement of account {} with {} records",
+                    source.name,
+                    parser.implementation.name(),
+                    data.config.account_nickname(&history.account_name),
+                    history.records.len()
+                ),
+                Err(e) => println!(
+                    "{}: {} statement, but {e}",
+                    source.name,
+                    parser.implementation.name()
+                ),
+            },
+            Err(e) => println!("{}: ignored, {e}", source.name),
+        }
+    }
+    for ignored in scan_result.ignored.iter() {
+        let reason = match ignored.reason {
+            IgnoreReason::Excluded => "excluded",
+            IgnoreReason::NotIncluded => "not included",
+            IgnoreReason::NonUtf8Path => "path is not valid UTF-8",
+            IgnoreReason::UnreadableArchive => "unreadable archive",
+        };
+        println!("{}: ignored, {reason}", ignored.name);
+    }
+}
+
+fn validate_statements(data: &DataDir) {
+    let account_histories = load_account_histories(data, false);
+
+    let discrepancies = validate(&account_histories);
+
+    if discrepancies.is_empty() {
+        println!("No discrepancies found.");
+    }
+    for discrepancy in discrepancies {
+        println!("{discrepancy}");
+    }
+}
+
+fn read_merged_records(
+    data: &DataDir,
+    from_store: bool,
+    start_date: Option<NaiveDate>,
+    account_name: Option<&str>,
+) -> Vec<AccountRecord> {
+    read_merged_statements(data, from_store, start_date, account_name).0
+}
+
+/// Returns the merged records and the periods covered by the statements of
+/// each account.
+fn read_merged_statements(
+    data: &DataDir,
+    from_store: bool,
+    start_date: Option<NaiveDate>,
+    account_name: Option<&str>,
+) -> (Vec<AccountRecord>, Vec<StatementPeriod>) {
+    let account_histories: Vec<AccountHistory> = load_account_histories(data, from_store)
         .into_iter()
-        .filter_map(|r| Some(r.unwrap()))
-        .filter(|r| r.path().is_file())
-        .filter(|r| r.file_name() != own_account_rules_file)
-        .map(|r| String::from_str(&r.path().to_str().unwrap()).unwrap())
+        .filter(|h| {
+            account_name.is_none_or(|name| data.config.selects_account(name, &h.account_name))
+        })
         .collect();
 
-    let account_histories: Vec<AccountHistory> = file_paths
+    // depots do not contribute records to the merged cash bookings
+    let cash_histories: Vec<AccountHistory> = account_histories
         .iter()
-        .map(|path| {
-            let parser = ParserFactory::create(path);
-            match parser {
-                Ok(p) => p.parse(path),
-                Err(_) => Err(ParserError::FileReadError),
-            }
+        .filter(|h| !h.account_type.is_depot())
+        .cloned()
+        .collect();
+    let covered_periods = covered_periods_by_account(&cash_histories)
+        .into_iter()
+        .map(|period| StatementPeriod {
+            from: start_date.map_or(period.from, |d| d.max(period.from)),
+            to: period.to,
         })
-        .filter_map(|r| r.ok())
         .collect();
 
-    let own_account_rules_file_path = std::path::Path::new(dir_path).join(own_account_rules_file);
+    let merged_records = merge_histories(data, &account_histories, start_date);
 
-    let own_account_rules = read_merge_rules(own_account_rules_file_path.to_str().unwrap())
-        .expect("Could not read merge rules");
+    (merged_records, covered_periods)
+}
 
-    let all_records: Vec<Vec<AccountRecord>> =
-        account_histories.into_iter().map(|h| h.records).collect();
+/// The records of all histories as merged by `merge_and_net_refunds`,
+/// with the refunds netted against their purchases.
+fn merge_histories(
+    data: &DataDir,
+    account_histories: &[AccountHistory],
+    start_date: Option<NaiveDate>,
+) -> Vec<AccountRecord> {
+    merge_and_net_refunds(data, account_histories, start_date).records
+}
 
-    let start_date = start_date.map(|s| NaiveDate::parse_from_str(s.as_ref(), "%d.%m.%Y"));
+/// Merges the records of all histories, removes transfers between own
+/// accounts including reconciled credit card settlements and securities
+/// trades, normalizes the counterparties, applies the manual overrides
+/// and nets the refunds against their purchases. Pending bookings are
+/// left out unless they are included by the configuration.
+fn merge_and_net_refunds(
+    data: &DataDir,
+    account_histories: &[AccountHistory],
+    start_date: Option<NaiveDate>,
+) -> RefundMatching {
+    let own_account_rules = read_optional_file(
+        &data.file(DataFile::OwnAccountRules),
+        read_merge_rules,
+        MergeRuleReadingError::FileError,
+    );
 
-    let merged_records = match start_date {
-        Some(start_date) => {
-            merge_records_from_date(all_records, own_account_rules, start_date.unwrap())
-        }
+    let counterparty_aliases = read_optional_file(
+        &data.file(DataFile::CounterpartyAliases),
+        read_counterparty_aliases,
+        CounterpartyAliasReadingError::FileError,
+    );
+    let counterparty_normalizer = CounterpartyNormalizer::new(&counterparty_aliases)
+        .expect("Could not read counterparty aliases");
+
+    let settlements = reconcile_card_settlements(account_histories);
+    let account_histories = remove_settlements(account_histories, &settlements);
+
+    // buying and selling securities moves money between the cash accounts
+    // and the depots, neither side is an earning or a spending
+    let include_pending = data.config.include_pending.unwrap_or(false);
+    let all_records: Vec<Vec<AccountRecord>> = account_histories
+        .iter()
+        .filter(|h| !h.account_type.is_depot())
+        .map(|h| {
+            h.records
+                .iter()
+                .filter(|r| include_pending || !r.is_pending())
+                .filter(|r| !is_securities_trade(r))
+                .cloned()
+                .collect()
+        })
+        .collect();
+
+    let mut merged_records = match start_date {
+        Some(start_date) => merge_records_from_date(all_records, own_account_rules, start_date),
         None => merge_records(all_records, own_account_rules),
     };
 
+    counterparty_normalizer.apply(&mut merged_records);
+
+    let overrides = read_optional_file(
+        &data.file(DataFile::Overrides),
+        read_overrides,
+        OverrideReadingError::FileError,
+    );
+
+    net_refunds(
+        apply_overrides(merged_records, &overrides).expect("Invalid overrides"),
+        REFUND_WINDOW_DAYS,
+    )
+}
+
+fn read_optional_planned_items(data: &DataDir) -> Vec<PlannedItem> {
+    read_optional_file(
+        &data.file(DataFile::PlannedItems),
+        read_planned_items,
+        PlannedItemReadingError::FileError,
+    )
+}
+
+fn forecast_balances(data: &DataDir, months: usize, threshold: f64, from_store: bool) {
+    let account_histories = load_account_histories(data, from_store);
+    let planned_items = read_optional_planned_items(data);
+
+    let forecasts = forecast(&account_histories, months, &planned_items);
+    if forecasts.is_empty() {
+        println!("No accounts found.");
+        return;
+    }
+
+    let dates: Vec<NaiveDate> = forecasts
+        .iter()
+        .flat_map(|f| f.points.iter().map(|p| p.date))
+        .unique()
+        .sorted()
+        .collect();
+
+    print!("{:<12}", "Date");
+    for f in forecasts.iter() {
+        print!("{:>30}", f.account_name);
+    }
+    println!();
+
+    print!("{:<12}", "Current");
+    for f in forecasts.iter() {
+        print!("{:>30.2}", f.start.balance);
+    }
+    println!();
+
+    for date in dates {
+        print!("{:<12}", date.to_string());
+        for f in forecasts.iter() {
+            match f.points.iter().find(|p| p.date == date) {
+                Some(point) => print!("{:>30.2}", point.balance),
+                None => print!("{:>30}", ""),
+            }
+        }
+        println!();
+    }
+
+    println!();
+    for f in forecasts.iter() {
+        println!(
+            "{}: {} recurring series, variable bookings {:.2} per month",
+            f.account_name,
+            f.recurring_series.len(),
+            f.variable_flow
+        );
+        for warning in f.warnings(threshold) {
+            println!(
+                "  Warning: projected balance {:.2} on {} is below {threshold:.2}",
+                warning.balance, warning.date
+            );
+        }
+    }
+}
+
+fn show_kpis(data: &DataDir, months: usize, from_store: bool) {
+    let account_histories = load_account_histories(data, from_store);
+    let records = merge_histories(data, &account_histories, None);
+
+    let Some(as_of) = records.iter().map(|r| r.date).max() else {
+        println!("No records found.");
+        return;
+    };
+
+    let recurring_series = find_recurring_payments(&records, as_of);
+    let monthly_reports = MonthlyReports::create(records);
+    let kpis = monthly_kpis(&monthly_reports, &account_histories, &recurring_series);
+
+    let percent = |value: Option<f64>| match value {
+        Some(value) => format!("{:>7.1} %", value * 100.0),
+        None => format!("{:>9}", "n/a"),
+    };
+    let months_of = |value: Option<f64>| match value {
+        Some(value) => format!("{value:>6.1}"),
+        None => format!("{:>6}", "n/a"),
+    };
+    let print_kpis = |label: &str, kpis: &Kpis| {
+        println!(
+            "  {label:<10} savings rate {}  runway {} months  fixed costs {}  emergency fund {}",
+            percent(kpis.savings_rate),
+            months_of(kpis.runway_months),
+            percent(kpis.fixed_cost_ratio),
+            percent(kpis.emergency_fund_coverage)
+        );
+    };
+
+    let shown = kpis.len().saturating_sub(months);
+    for month_kpis in kpis[shown..].iter().rev() {
+        println!(
+            "{}  liquid balance {:.2}  net worth {:.2}",
+            month_kpis.month, month_kpis.liquid_balance, month_kpis.net_worth
+        );
+        print_kpis("month", &month_kpis.month_kpis);
+        print_kpis(
+            &format!("{TRAILING_MONTHS} months"),
+            &month_kpis.trailing_kpis,
+        );
+    }
+}
+
+fn show_portfolio(data: &DataDir, from_store: bool) {
+    let account_histories = load_account_histories(data, from_store);
+    let Some(as_of) = account_histories
+        .iter()
+        .map(|h| h.current_balance_date)
+        .max()
+    else {
+        println!("No statements found.");
+        return;
+    };
+
+    let holdings = holdings_at(&account_histories, as_of);
+    if holdings.is_empty() {
+        println!("No depot holdings found.");
+    }
+    for depot in holdings.iter() {
+        println!(
+            "{} on {}: {:.2}",
+            depot.account_name, depot.current_balance_date, depot.current_balance
+        );
+        for p in depot.positions.iter() {
+            println!(
+                "  {} {:<40} {:>10.3}  cost {:>10.2}  value {:>10.2}  gain {:>10.2}",
+                p.isin,
+                p.name,
+                p.quantity,
+                p.cost_basis,
+                p.market_value,
+                p.gain()
+            );
+        }
+    }
+
+    let liquid_balance = liquid_balance_at(&account_histories, as_of);
+    println!();
+    println!(
+        "Net worth on {as_of}: {:.2} (liquid {liquid_balance:.2}, depots {:.2})",
+        net_worth_at(&account_histories, as_of),
+        depot_value_at(&account_histories, as_of)
+    );
+}
+
+fn list_card_settlements(data: &DataDir, from_store: bool) {
+    let account_histories = load_account_histories(data, from_store);
+
+    let settlements = reconcile_card_settlements(&account_histories);
+
+    if settlements.is_empty() {
+        println!("No credit card settlements found.");
+    }
+    for settlement in settlements.iter() {
+        println!("{settlement}");
+    }
+}
+
+fn list_refunds(data: &DataDir, from_store: bool) {
+    let account_histories = load_account_histories(data, from_store);
+    let matching = merge_and_net_refunds(data, &account_histories, None);
+
+    if matching.matches.is_empty() {
+        println!("No refunds found.");
+    }
+    for refund_match in matching.matches.iter() {
+        println!("{refund_match}");
+    }
+
+    if !matching.unmatched.is_empty() {
+        println!();
+        println!("Refunds without purchase within {REFUND_WINDOW_DAYS} days:");
+        for record in matching.unmatched.iter() {
+            println!("  {record}");
+        }
+    }
+}
+
+fn list_recurring_payments(data: &DataDir, from_store: bool) {
+    let records = read_merged_records(data, from_store, None, None);
+
+    let Some(as_of) = records.iter().map(|r| r.date).max() else {
+        println!("No records found.");
+        return;
+    };
+
+    let series = find_recurring_payments(&records, as_of);
+    if series.is_empty() {
+        println!("No recurring payments found.");
+        return;
+    }
+
+    let active_total: f64 = series
+        .iter()
+        .filter(|s| !s.stopped)
+        .map(|s| s.annualized_amount)
+        .sum();
+
+    for s in series.iter() {
+        println!("{s}");
+    }
+    println!();
+    println!("Annualized total of active series: {active_total:.2}");
+}
+
+/// Merged records do not know their account, so the account is selected
+/// before merging.
+fn search_records(
+    data: &DataDir,
+    filter: &RecordFilter,
+    order: RecordOrder,
+    descending: bool,
+    from_store: bool,
+) {
+    let records = read_merged_records(data, from_store, None, None);
+    let category_rules = read_optional_category_rules(data);
+
+    let matches = filter.apply(&records, &category_rules, order, descending);
+
+    for record in matches.iter() {
+        println!("{record}");
+    }
+    let total: f64 = matches.iter().map(|r| r.amount).sum();
+    println!();
+    println!("{} records, total {total:.2}", matches.len());
+}
+
+fn read_optional_category_rules(data: &DataDir) -> Vec<CategoryRule> {
+    read_optional_file(
+        &data.file(DataFile::CategoryRules),
+        read_category_rules,
+        CategoryRuleReadingError::FileError,
+    )
+}
+
+fn list_periods(data: &DataDir, granularity: Granularity, from_store: bool) {
+    let records = read_merged_records(data, from_store, None, None);
+    let category_rules = read_optional_category_rules(data);
+
+    let monthly_reports = MonthlyReports::create(records);
+
+    for report in period_reports(&monthly_reports, granularity, &category_rules) {
+        println!(
+            "{}: earnings {:.2}, spendings {:.2}, balance {:.2}",
+            report.period,
+            report.totals.earnings,
+            report.totals.spendings,
+            report.totals.balance()
+        );
+        for (category, total) in report.totals.categories.iter() {
+            println!("  {category}: {total:.2}");
+        }
+        for (tag, total) in report.totals.tags.iter() {
+            println!("  [{tag}]: {total:.2}");
+        }
+    }
+}
+
+fn print_comparison(title: &str, comparison: &PeriodComparison) {
+    println!(
+        "{title}: {} compared with {}",
+        comparison.current.period, comparison.previous.period
+    );
+    println!(
+        "  Earnings: {:.2} vs {:.2}, {}",
+        comparison.current.totals.earnings,
+        comparison.previous.totals.earnings,
+        comparison.earnings()
+    );
+    println!(
+        "  Spendings: {:.2} vs {:.2}, {}",
+        comparison.current.totals.spendings,
+        comparison.previous.totals.spendings,
+        comparison.spendings()
+    );
+    println!(
+        "  Balance: {:.2} vs {:.2}, {}",
+        comparison.current.totals.balance(),
+        comparison.previous.totals.balance(),
+        comparison.balance()
+    );
+    for (category, delta) in comparison.categories() {
+        println!("  {category}: {delta}");
+    }
+}
+
+fn compare_with_previous_year(data: &DataDir, month: Option<YearMonth>, from_store: bool) {
+    let records = read_merged_records(data, from_store, None, None);
+    let category_rules = read_optional_category_rules(data);
+
+    let Some(month) = month.or(records.iter().map(|r| YearMonth::of(r.date)).max()) else {
+        println!("No records found.");
+        return;
+    };
+
+    let monthly_reports = MonthlyReports::create(records);
+
+    print_comparison(
+        "Month",
+        &compare_with_last_year(&monthly_reports, month, &category_rules),
+    );
+    println!();
+    print_comparison(
+        "Year to date",
+        &compare_year_to_date(&monthly_reports, month, &category_rules),
+    );
+}
+
+fn list_counterparties(
+    data: &DataDir,
+    from: Option<String>,
+    to: Option<String>,
+    account_name: Option<String>,
+    format: Option<ReportFormat>,
+    from_store: bool,
+) {
+    let records = read_merged_records(data, from_store, None, account_name.as_deref());
+
+    let report = CounterpartyReport::create(
+        &records,
+        from.as_deref().map(|d| data.parse_date(d)),
+        to.as_deref().map(|d| data.parse_date(d)),
+    );
+
+    let format = format.unwrap_or(match data.config.report_format {
+        Some(config::ReportFormat::Csv) => ReportFormat::Csv,
+        Some(config::ReportFormat::Text) | None => ReportFormat::Text,
+    });
+
+    match format {
+        ReportFormat::Text => print_counterparty_report(&report),
+        ReportFormat::Csv => write_counterparty_csv(&report).expect("Could not write csv"),
+    }
+}
+
+fn print_counterparty_report(report: &CounterpartyReport) {
+    if report.summaries.is_empty() {
+        println!("No records found.");
+        return;
+    }
+
+    for summary in report.summaries.iter() {
+        println!(
+            "{}: {:.2} in {} bookings (average {:.2}) from {} to {}",
+            summary.counterparty,
+            summary.total,
+            summary.count,
+            summary.average_amount,
+            summary.first_date,
+            summary.last_date
+        );
+        let trend = summary
+            .monthly_totals
+            .iter()
+            .map(|(month, total)| format!("{month}: {total:.2}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+        println!("  {trend}");
+    }
+}
+
+fn write_counterparty_csv(report: &CounterpartyReport) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+
+    let mut header = vec![
+        "counterparty".to_string(),
+        "total".to_string(),
+        "count".to_string(),
+        "average".to_string(),
+        "first".to_string(),
+        "last".to_string(),
+    ];
+    header.extend(report.months.iter().map(|m| m.to_string()));
+    writer.write_record(&header)?;
+
+    for summary in report.summaries.iter() {
+        let mut row = vec![
+            summary.counterparty.clone(),
+            format!("{:.2}", summary.total),
+            summary.count.to_string(),
+            format!("{:.2}", summary.average_amount),
+            summary.first_date.to_string(),
+            summary.last_date.to_string(),
+        ];
+        row.extend(
+            summary
+                .monthly_totals
+                .iter()
+                .map(|(_, total)| format!("{total:.2}")),
+        );
+        writer.write_record(&row)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn statistics_header(reports: &MonthlyReports) -> String {
+    let rolling_window = 3;
+
+    let format_statistics = |name: &str, statistics: MonthlyStatistics| {
+        format!(
+            "{name}: mean {:.2}, median {:.2}, trimmed mean {:.2}, standard deviation {:.2}\n",
+            statistics.mean,
+            statistics.median,
+            statistics.trimmed_mean,
+            statistics.standard_deviation
+        )
+    };
+
+    let mut header = format!("Complete months: {}\n", reports.reports.len());
+    if !reports.excluded_months.is_empty() {
+        header += &format!(
+            "Excluded incomplete months: {}\n",
+            reports.excluded_months.iter().join(", ")
+        );
+    }
+    header += &format_statistics("Earnings", reports.earnings_statistics());
+    header += &format_statistics("Spendings", reports.spendings_statistics());
+
+    let rolling_averages = reports.rolling_averages(rolling_window);
+    if !rolling_averages.is_empty() {
+        header += &format!("Rolling {rolling_window} month averages:\n");
+        for average in rolling_averages.iter().rev() {
+            header += &format!(
+                "  {}: earnings {:.2}, spendings {:.2}\n",
+                average.month, average.earnings, average.spendings
+            );
+        }
+    }
+
+    header
+}
+
+fn anomalies_section(anomalies: &[Anomaly]) -> String {
+    let mut section = format!("Anomalies: {}\n", anomalies.len());
+    for anomaly in anomalies.iter().rev() {
+        section += &format!("  {anomaly}\n");
+    }
+    section
+}
+
+fn list_anomalies(
+    data: &DataDir,
+    from: Option<String>,
+    new_counterparty_threshold: f64,
+    from_store: bool,
+) {
+    let records = read_merged_records(data, from_store, None, None);
+    let category_rules = read_optional_category_rules(data);
+
+    let config = AnomalyConfig {
+        new_counterparty_threshold,
+        ..AnomalyConfig::default()
+    };
+    let from = from.as_deref().map(|d| data.parse_date(d));
+
+    let anomalies: Vec<Anomaly> = find_anomalies(&records, &category_rules, &config)
+        .into_iter()
+        .filter(|a| from.is_none_or(|from| a.record().date >= from))
+        .collect();
+
+    if anomalies.is_empty() {
+        println!("No anomalies found.");
+    }
+    for anomaly in anomalies {
+        println!("{anomaly}");
+    }
+}
+
+fn generate_balance_sheet(
+    data: &DataDir,
+    report_path: &str,
+    start_date: Option<String>,
+    from_store: bool,
+) {
+    let start_date = start_date.map(|s| data.parse_date(&s));
+
+    let (merged_records, covered_periods) =
+        read_merged_statements(data, from_store, start_date, None);
+
+    let mut complete_months = MonthlyReports::create(merged_records.clone());
+    complete_months.exclude_incomplete_months(&covered_periods);
+
+    let anomalies = find_anomalies(
+        &merged_records,
+        &read_optional_category_rules(data),
+        &AnomalyConfig::default(),
+    );
+
     let monthly_reports = MonthlyReports::create(merged_records);
 
     let report_contents = format!(
-        "Average Earnings: {}
-Average Spendings: {}
-
+        "{}
+{}
 {}",
-        monthly_reports.average_earnings(),
-        monthly_reports.average_spendings(),
+        statistics_header(&complete_months),
+        anomalies_section(&anomalies),
         monthly_reports
             .reports
             .into_iter()
@@ -116,66 +1276,135 @@ Average Spendings: {}
     fs::write(report_path, report_contents).expect("Could not write report");
 }
 
-fn plot_accounts() {
-    let parser = BankStatementParser {
-        implementation: Box::new(DkbAccountParser {}),
+fn export_journal(data: &DataDir, format: ExportFormat, output_path: Option<String>) {
+    let account_histories = read_account_histories(data);
+
+    let own_account_rules = read_optional_file(
+        &data.file(DataFile::OwnAccountRules),
+        read_merge_rules,
+        MergeRuleReadingError::FileError,
+    );
+
+    let category_rules = read_optional_category_rules(data);
+
+    let export_config = read_optional_file(
+        &data.file(DataFile::ExportConfig),
+        read_export_config,
+        ExportConfigReadingError::FileError,
+    );
+
+    let journal = Journal::create(
+        &account_histories,
+        &export_config,
+        &category_rules,
+        &own_account_rules,
+    );
+
+    let (exporter, default_output_path): (Box<dyn JournalExporter>, &str) = match format {
+        ExportFormat::Ledger => (Box::new(LedgerExporter {}), "./accounts.journal"),
+        ExportFormat::Beancount => (Box::new(BeancountExporter {}), "./accounts.beancount"),
     };
 
-    let my_account_history = parser
-        .parse("/home/andreas/Dokumente/Konten/1018793511.csv")
-        .unwrap();
+    let output_path = output_path
+        .or(data.config.journal_path.clone())
+        .unwrap_or(default_output_path.to_string());
 
-    let our_account_history = parser
-        .parse("/home/andreas/Dokumente/Konten/1050155058.csv")
-        .unwrap();
+    fs::write(output_path, exporter.export(&journal)).expect("Could not write journal");
+}
 
-    let root_area =
-        BitMapBackend::new("/home/andreas/Dokumente/Konten/kontostand.png", (1200, 800))
-            .into_drawing_area();
-    root_area.fill(&WHITE).unwrap();
+fn plot_accounts(data: &DataDir, output_path: &str, from_store: bool, forecast_months: usize) {
+    let account_histories: Vec<AccountHistory> = load_account_histories(data, from_store)
+        .into_iter()
+        .filter(|h| !h.account_type.is_depot())
+        .collect();
 
-    let start_date = chrono::NaiveDate::from_ymd_opt(2021, 9, 1).unwrap();
-    let end_date = chrono::NaiveDate::from_ymd_opt(2024, 10, 1).unwrap();
+    let forecasts: Vec<(String, Vec<(NaiveDate, f64)>)> = if forecast_months > 0 {
+        forecast(
+            &account_histories,
+            forecast_months,
+            &read_optional_planned_items(data),
+        )
+        .into_iter()
+        .map(|f| {
+            let points = std::iter::once(&f.start)
+                .chain(f.points.iter())
+                .map(|p| (p.date, p.balance))
+                .collect();
+            (f.account_name, points)
+        })
+        .collect()
+    } else {
+        vec![]
+    };
+
+    let balances: Vec<Vec<(NaiveDate, f64)>> = account_histories
+        .iter()
+        .map(|h| match h.opening_anchor() {
+            Some(opening) => {
+                h.daily_balances(opening.date, h.current_balance_date, &h.current_anchor())
+            }
+            None => vec![],
+        })
+        .collect();
+
+    let all_balances = || {
+        balances
+            .iter()
+            .flatten()
+            .chain(forecasts.iter().flat_map(|(_, points)| points.iter()))
+    };
+    let (Some(start_date), Some(end_date)) = (
+        all_balances().map(|(d, _)| *d).min(),
+        all_balances().map(|(d, _)| *d).max(),
+    ) else {
+        println!("No records to plot");
+        return;
+    };
+    let min_balance = all_balances().map(|(_, b)| *b).fold(0.0, f64::min);
+    let max_balance = all_balances().map(|(_, b)| *b).fold(0.0, f64::max);
+
+    let root_area = BitMapBackend::new(output_path, (1200, 800)).into_drawing_area();
+    root_area.fill(&WHITE).unwrap();
 
     let mut ctx = ChartBuilder::on(&root_area)
         .set_label_area_size(LabelAreaPosition::Left, 80)
         .set_label_area_size(LabelAreaPosition::Bottom, 40)
-        .caption(&my_account_history.account_name, ("sans-serif", 40))
-        .build_cartesian_2d(start_date..end_date, 0.0..120000.0)
+        .caption("Account balances", ("sans-serif", 40))
+        .build_cartesian_2d(start_date..end_date, min_balance..max_balance * 1.1)
         .unwrap();
 
     ctx.configure_mesh().draw().unwrap();
 
-    let dates: Vec<chrono::NaiveDate> = my_account_history.records.iter().map(|r| r.date).collect();
-    let balances: Vec<(chrono::NaiveDate, f64)> = dates
-        .into_iter()
-        .map(|d| (d, my_account_history.get_balance_at(d).unwrap()))
-        .collect();
-
-    let our_dates: Vec<chrono::NaiveDate> =
-        our_account_history.records.iter().map(|r| r.date).collect();
-    let our_balances: Vec<(chrono::NaiveDate, f64)> = our_dates
-        .into_iter()
-        .map(|d| (d, our_account_history.get_balance_at(d).unwrap()))
-        .collect();
+    for (i, (history, balances)) in account_histories.iter().zip(balances).enumerate() {
+        let color = Palette99::pick(i).to_rgba();
 
-    ctx.draw_series(
-        AreaSeries::new(
-            balances,      // The data iter
-            0.0,           // Baseline
-            &RED.mix(0.2), // Make the series opac
+        ctx.draw_series(
+            AreaSeries::new(
+                balances,       // The data iter
+                0.0,            // Baseline
+                color.mix(0.2), // Make the series opac
+            )
+            .border_style(color), // Make a brighter border
         )
-        .border_style(&RED), // Make a brighter border
-    )
-    .unwrap();
+        .unwrap()
+        .label(&history.account_name)
+        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled()));
+    }
 
-    ctx.draw_series(
-        AreaSeries::new(
-            our_balances,   // The data iter
-            0.0,            // Baseline
-            &BLUE.mix(0.2), // Make the series opac
-        )
-        .border_style(&BLUE), // Make a brighter border
-    )
-    .unwrap();
+    for (account_name, points) in forecasts {
+        let i = account_histories
+            .iter()
+            .position(|h| h.account_name == account_name)
+            .unwrap_or(0);
+        let color = Palette99::pick(i).to_rgba();
+
+        ctx.draw_series(DashedLineSeries::new(points, 10, 5, color.stroke_width(2)))
+            .unwrap();
+    }
+
+    ctx.configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .unwrap();
 }
@@ -1,3 +1,10 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration};
+use itertools::Itertools;
+
+use super::portfolio::Position;
+use super::year_month::YearMonth;
 use super::*;
 
 #[derive(Debug, Error, PartialEq)]
@@ -6,28 +13,217 @@ pub enum AccountHistoryError {
     DateOutOfBounds,
 }
 
-#[derive(Debug, PartialEq)]
+/// The date range a bank statement reports to cover.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct StatementPeriod {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl StatementPeriod {
+    pub fn union(&self, other: &StatementPeriod) -> StatementPeriod {
+        StatementPeriod {
+            from: self.from.min(other.from),
+            to: self.to.max(other.to),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct AccountHistory {
     pub account_name: String,
     pub account_type: AccountType,
     pub current_balance_date: NaiveDate,
     pub current_balance: f64,
+    pub period: Option<StatementPeriod>,
     pub records: Vec<AccountRecord>,
+    /// The securities of a depot as of the balance date, only known from
+    /// holdings exports.
+    pub positions: Vec<Position>,
+}
+
+/// A known balance at the end of a day from which other balances are
+/// reconstructed.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BalanceAnchor {
+    pub date: NaiveDate,
+    pub balance: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MonthlyBalance {
+    pub month: YearMonth,
+    pub min: f64,
+    pub max: f64,
+    pub average: f64,
+    pub closing: f64,
 }
 
 impl AccountHistory {
+    /// Returns the balance at the end of the given day. Dates before the
+    /// start of the statement period (or the oldest record, if the period
+    /// is unknown) are out of bounds.
     pub fn get_balance_at(&self, date: NaiveDate) -> Result<f64, AccountHistoryError> {
-        let mut current_balance = self.current_balance;
-        for record in self.records.iter() {
-            if date >= record.date {
-                return Ok(current_balance);
+        match self.first_known_date() {
+            Some(first_date) if date >= first_date => {
+                Ok(self.balance_at(date, &self.current_anchor()))
             }
+            _ => Err(AccountHistoryError::DateOutOfBounds),
+        }
+    }
 
-            current_balance -= record.amount;
+    /// The balance reported by the statement.
+    pub fn current_anchor(&self) -> BalanceAnchor {
+        BalanceAnchor {
+            date: self.current_balance_date,
+            balance: self.current_balance,
         }
+    }
 
-        Err(AccountHistoryError::DateOutOfBounds)
+    /// The balance at the end of the day before the statement period, or
+    /// before the oldest record if the period is unknown.
+    pub fn opening_anchor(&self) -> Option<BalanceAnchor> {
+        let date = self.first_known_date()? - Duration::days(1);
+        Some(BalanceAnchor {
+            date,
+            balance: self.balance_at(date, &self.current_anchor()),
+        })
     }
+
+    /// Reconstructs the balance at the end of the given day from a known
+    /// balance, forward or backward in time. The order of the records does
+    /// not matter, records not contained in the balance are skipped.
+    pub fn balance_at(&self, date: NaiveDate, anchor: &BalanceAnchor) -> f64 {
+        let (from, to, sign) = if date >= anchor.date {
+            (anchor.date, date, 1.0)
+        } else {
+            (date, anchor.date, -1.0)
+        };
+
+        let bookings: f64 = self
+            .records
+            .iter()
+            .filter(|r| r.counts_in_balance())
+            .filter(|r| r.date > from && r.date <= to)
+            .map(|r| r.amount)
+            .sum();
+
+        anchor.balance + sign * bookings
+    }
+
+    /// Returns the balance at the end of every day from `from` to `to`.
+    pub fn daily_balances(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        anchor: &BalanceAnchor,
+    ) -> Vec<(NaiveDate, f64)> {
+        let mut bookings_per_day: HashMap<NaiveDate, f64> = HashMap::new();
+        for record in self.records.iter().filter(|r| r.counts_in_balance()) {
+            *bookings_per_day.entry(record.date).or_default() += record.amount;
+        }
+
+        let mut balance = self.balance_at(from, anchor);
+        let mut balances = Vec::new();
+
+        for date in from.iter_days().take_while(|d| *d <= to) {
+            if date > from {
+                balance += bookings_per_day.get(&date).unwrap_or(&0.0);
+            }
+            balances.push((date, balance));
+        }
+
+        balances
+    }
+
+    /// Returns the minimum, maximum, average and closing balance of every
+    /// month between `from` and `to`, based on end of day balances.
+    pub fn monthly_balances(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        anchor: &BalanceAnchor,
+    ) -> Vec<MonthlyBalance> {
+        self.daily_balances(from, to, anchor)
+            .into_iter()
+            .chunk_by(|(date, _)| YearMonth::new(date.year(), date.month0()))
+            .into_iter()
+            .map(|(month, days)| {
+                let balances: Vec<f64> = days.map(|(_, balance)| balance).collect();
+                MonthlyBalance {
+                    month,
+                    min: balances.iter().cloned().fold(f64::INFINITY, f64::min),
+                    max: balances.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                    average: balances.iter().sum::<f64>() / balances.len() as f64,
+                    closing: *balances.last().unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    /// The days for which the statement knows all bookings: the statement
+    /// period, extended to the balance date, or the oldest record up to the
+    /// balance date if the period is unknown.
+    pub fn covered_period(&self) -> Option<StatementPeriod> {
+        Some(StatementPeriod {
+            from: self.first_known_date()?,
+            to: match self.period {
+                Some(period) => period.to.max(self.current_balance_date),
+                None => self.current_balance_date,
+            },
+        })
+    }
+
+    /// Gives every record its stable identifier. Equal bookings are counted
+    /// from the oldest one, which usually is part of all overlapping
+    /// exports, so that the same booking gets the same identifier. The order
+    /// of the records does not matter.
+    pub fn assign_transaction_ids(&mut self) {
+        // statements list the newest booking of a day first
+        let oldest_first: Vec<usize> = (0..self.records.len())
+            .rev()
+            .sorted_by_key(|&i| self.records[i].date)
+            .collect();
+
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        for i in oldest_first {
+            let record = &mut self.records[i];
+            let occurrence = occurrences.entry(record.identity()).or_default();
+            record.id = Some(record.transaction_id(&self.account_name, *occurrence));
+            *occurrence += 1;
+        }
+    }
+
+    /// Sets the account name of every record to the one of the history, so
+    /// that merged records can still be told apart by their account.
+    pub fn name_records(&mut self) {
+        for record in self.records.iter_mut() {
+            record.account_name = Some(self.account_name.clone());
+        }
+    }
+
+    fn first_known_date(&self) -> Option<NaiveDate> {
+        match self.period {
+            Some(period) => Some(period.from),
+            None => self.records.iter().map(|r| r.date).min(),
+        }
+    }
+}
+
+/// The periods covered by the statements of each account. Overlapping
+/// statements of the same account are combined.
+pub fn covered_periods_by_account(histories: &[AccountHistory]) -> Vec<StatementPeriod> {
+    histories
+        .iter()
+        .into_group_map_by(|h| h.account_name.clone())
+        .into_values()
+        .filter_map(|exports| {
+            exports
+                .iter()
+                .filter_map(|h| h.covered_period())
+                .reduce(|a, b| a.union(&b))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -74,4 +270,217 @@ mod tests {
             Err(AccountHistoryError::DateOutOfBounds)
         )
     }
+
+    #[test]
+    fn balances_do_not_depend_on_the_order_of_records() {
+        let mut history = given_a_history();
+        history.records.reverse();
+
+        assert_relative_eq!(history.get_balance_at(str_date("3.3.2024")).unwrap(), 50.0);
+        assert_relative_eq!(history.get_balance_at(str_date("1.3.2024")).unwrap(), 100.0);
+    }
+
+    #[test]
+    fn bookings_outside_the_balance_are_skipped() {
+        let mut history = given_a_history();
+        history.records.push(AccountRecord {
+            status: BookingStatus::Pending,
+            ..new_record(-500.0, "6.3.2024")
+        });
+        history.records.push(AccountRecord {
+            status: BookingStatus::NotInBalance,
+            ..new_record(-70.0, "4.3.2024")
+        });
+
+        assert_relative_eq!(history.get_balance_at(str_date("3.3.2024")).unwrap(), 50.0);
+        let anchor = history.current_anchor();
+        assert_eq!(
+            history.daily_balances(str_date("5.3.2024"), str_date("6.3.2024"), &anchor),
+            vec![(str_date("5.3.2024"), 350.0), (str_date("6.3.2024"), 350.0)]
+        );
+    }
+
+    #[test]
+    fn transaction_ids_are_stable_across_overlapping_exports() {
+        let mut newer = given_a_history();
+        newer.records.insert(1, new_record(-50.0, "3.3.2024"));
+        newer.records.insert(0, new_record(20.0, "6.3.2024"));
+        newer.assign_transaction_ids();
+        let mut older = given_a_history();
+        older.records.remove(0);
+        older.assign_transaction_ids();
+
+        let ids: Vec<&str> = newer
+            .records
+            .iter()
+            .filter_map(|r| r.id.as_deref())
+            .unique()
+            .collect();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(older.records[0].id, newer.records[3].id);
+        assert_eq!(older.records[1].id, newer.records[4].id);
+
+        let mut other_account = given_a_history();
+        other_account.account_name = "DE02120300000000202051".to_string();
+        other_account.assign_transaction_ids();
+        assert_ne!(other_account.records[2].id, newer.records[4].id);
+    }
+
+    #[test]
+    fn transaction_ids_do_not_depend_on_the_order_of_the_records() {
+        let mut newest_first = given_a_history();
+        newest_first.records.insert(1, new_record(-50.0, "3.3.2024"));
+        newest_first.records.insert(1, new_record(-50.0, "3.3.2024"));
+        let mut oldest_first = newest_first.clone();
+        oldest_first.records.reverse();
+
+        newest_first.assign_transaction_ids();
+        oldest_first.assign_transaction_ids();
+
+        let ids = |history: &AccountHistory| -> Vec<String> {
+            history
+                .records
+                .iter()
+                .filter_map(|r| r.id.clone())
+                .sorted()
+                .collect()
+        };
+        assert_eq!(ids(&newest_first), ids(&oldest_first));
+    }
+
+    #[test]
+    fn the_coverage_is_combined_per_account() {
+        let statement = |account_name: &str, from: &str, to: &str| AccountHistory {
+            account_name: account_name.to_string(),
+            current_balance_date: str_date(to),
+            period: Some(StatementPeriod {
+                from: str_date(from),
+                to: str_date(to),
+            }),
+            ..given_a_history()
+        };
+        let histories = vec![
+            statement("DKB", "1.1.2024", "31.3.2024"),
+            statement("DKB", "1.3.2024", "30.6.2024"),
+            statement("ING", "1.2.2024", "31.5.2024"),
+        ];
+
+        let periods: Vec<(NaiveDate, NaiveDate)> = covered_periods_by_account(&histories)
+            .iter()
+            .map(|p| (p.from, p.to))
+            .sorted()
+            .collect();
+
+        assert_eq!(
+            periods,
+            vec![
+                (str_date("1.1.2024"), str_date("30.6.2024")),
+                (str_date("1.2.2024"), str_date("31.5.2024")),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_transaction_id_depends_on_the_contents() {
+        let record = new_record(-80.0, "5.3.2024");
+        let moved = new_record(-80.0, "6.3.2024");
+        let changed = new_record(-80.5, "5.3.2024");
+        let same = new_record(-80.0, "5.3.2024");
+
+        assert_eq!(record.transaction_id("DKB", 0), same.transaction_id("DKB", 0));
+        assert_ne!(record.transaction_id("DKB", 0), moved.transaction_id("DKB", 0));
+        assert_ne!(record.transaction_id("DKB", 0), changed.transaction_id("DKB", 0));
+        assert_ne!(record.transaction_id("DKB", 0), record.transaction_id("DKB", 1));
+        assert_eq!(record.transaction_id("DKB", 0).len(), 16);
+        assert!(record.transaction_id("DKB", 0).chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn bookings_differing_only_in_booking_text_are_counted_as_occurrences() {
+        let mut history = given_a_history();
+        history.records = vec![
+            AccountRecord {
+                other_side: Some("Edeka".to_string()),
+                booking_text: "Kartenzahlung".to_string(),
+                ..new_record(-80.0, "5.3.2024")
+            },
+            AccountRecord {
+                other_side: Some("Edeka".to_string()),
+                booking_text: "Lastschrift".to_string(),
+                ..new_record(-80.0, "5.3.2024")
+            },
+        ];
+        history.assign_transaction_ids();
+
+        assert_eq!(history.records[0].identity(), history.records[1].identity());
+        let account_name = history.account_name.as_str();
+        assert_eq!(history.records[0].id, Some(history.records[0].transaction_id(account_name, 1)));
+        assert_eq!(history.records[1].id, Some(history.records[1].transaction_id(account_name, 0)));
+    }
+
+    #[test]
+    fn balances_are_reconstructed_forward_from_an_opening_balance() {
+        let history = AccountHistory {
+            period: Some(StatementPeriod {
+                from: str_date("1.2.2024"),
+                to: str_date("6.3.2024"),
+            }),
+            ..given_a_history()
+        };
+
+        let opening = history.opening_anchor().unwrap();
+        assert_eq!(opening.date, str_date("31.1.2024"));
+        assert_relative_eq!(opening.balance, 0.0);
+
+        assert_relative_eq!(history.get_balance_at(str_date("15.2.2024")).unwrap(), 0.0);
+
+        let anchor = BalanceAnchor {
+            date: str_date("1.1.2024"),
+            balance: 1000.0,
+        };
+        assert_relative_eq!(history.balance_at(str_date("4.3.2024"), &anchor), 1050.0);
+        assert_relative_eq!(history.balance_at(str_date("31.12.2023"), &anchor), 1000.0);
+    }
+
+    #[test]
+    fn daily_balances_contain_every_day() {
+        let history = given_a_history();
+
+        let balances = history.daily_balances(
+            str_date("29.2.2024"),
+            str_date("4.3.2024"),
+            &history.current_anchor(),
+        );
+
+        assert_eq!(
+            balances,
+            vec![
+                (str_date("29.2.2024"), 0.0),
+                (str_date("1.3.2024"), 100.0),
+                (str_date("2.3.2024"), 100.0),
+                (str_date("3.3.2024"), 50.0),
+                (str_date("4.3.2024"), 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn monthly_balances_contain_minimum_maximum_and_average() {
+        let history = given_a_history();
+
+        let balances = history.monthly_balances(
+            str_date("28.2.2024"),
+            str_date("6.3.2024"),
+            &history.current_anchor(),
+        );
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].month, YearMonth::new(2024, 1));
+        assert_relative_eq!(balances[0].max, 0.0);
+        assert_eq!(balances[1].month, YearMonth::new(2024, 2));
+        assert_relative_eq!(balances[1].min, 50.0);
+        assert_relative_eq!(balances[1].max, 350.0);
+        assert_relative_eq!(balances[1].average, 1000.0 / 6.0);
+        assert_relative_eq!(balances[1].closing, 350.0);
+    }
 }
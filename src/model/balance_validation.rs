@@ -0,0 +1,307 @@
+use std::fmt::Display;
+
+use chrono::{Duration, NaiveDate};
+use itertools::Itertools;
+
+use super::account_history::AccountHistory;
+
+/// Amounts closer than this are considered equal.
+const TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Discrepancy {
+    /// The balance reported by one statement does not match the balance
+    /// reconstructed from another statement of the same account.
+    BalanceMismatch {
+        account_name: String,
+        date: NaiveDate,
+        reported: f64,
+        reconstructed: f64,
+        reconstructed_from: NaiveDate,
+    },
+    /// The running balance printed next to a booking does not match the
+    /// previous balance and the booking amount.
+    RecordBalanceMismatch {
+        account_name: String,
+        date: NaiveDate,
+        amount: f64,
+        reported: f64,
+        expected: f64,
+    },
+    /// No statement of the account covers these days.
+    Gap {
+        account_name: String,
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+}
+
+impl Display for Discrepancy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Discrepancy::BalanceMismatch {
+                account_name,
+                date,
+                reported,
+                reconstructed,
+                reconstructed_from,
+            } => write!(
+                f,
+                "{account_name}: balance on {date} is reported as {reported:.2}, \
+                 but the statement from {reconstructed_from} implies {reconstructed:.2} \
+                 (difference {:.2})",
+                reported - reconstructed
+            ),
+            Discrepancy::RecordBalanceMismatch {
+                account_name,
+                date,
+                amount,
+                reported,
+                expected,
+            } => write!(
+                f,
+                "{account_name}: booking of {amount:.2} on {date} reports a balance of \
+                 {reported:.2}, expected {expected:.2} (difference {:.2})",
+                reported - expected
+            ),
+            Discrepancy::Gap {
+                account_name,
+                from,
+                to,
+            } => write!(f, "{account_name}: no statement covers {from} to {to}"),
+        }
+    }
+}
+
+/// Checks the statements of all accounts for inconsistent balances and
+/// for periods which are not covered by any statement.
+pub fn validate(histories: &[AccountHistory]) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+    // the balance of a depot changes with the prices, not with its bookings
+    let histories: Vec<&AccountHistory> = histories
+        .iter()
+        .filter(|h| !h.account_type.is_depot())
+        .collect();
+
+    for history in histories.iter() {
+        discrepancies.extend(record_balance_mismatches(history));
+    }
+
+    let histories_by_account = histories
+        .into_iter()
+        .sorted_by(|a, b| a.account_name.cmp(&b.account_name))
+        .chunk_by(|h| h.account_name.clone());
+
+    for (_, account_histories) in histories_by_account.into_iter() {
+        let account_histories: Vec<&AccountHistory> = account_histories.collect();
+        discrepancies.extend(balance_mismatches(&account_histories));
+        discrepancies.extend(gaps(&account_histories));
+    }
+
+    discrepancies
+}
+
+/// The balance at the end of the day, if the statement covers the day.
+pub fn balance_at_end_of(history: &AccountHistory, date: NaiveDate) -> Option<f64> {
+    let opening = history.opening_anchor()?;
+    if date < opening.date || date > history.current_balance_date {
+        return None;
+    }
+
+    Some(history.balance_at(date, &history.current_anchor()))
+}
+
+fn record_balance_mismatches(history: &AccountHistory) -> Vec<Discrepancy> {
+    // statements with running balances list the newest booking first
+    let mut expected = history.current_balance;
+    let mut discrepancies = Vec::new();
+
+    for record in history.records.iter().filter(|r| r.counts_in_balance()) {
+        if let Some(reported) = record.balance {
+            if (reported - expected).abs() > TOLERANCE {
+                discrepancies.push(Discrepancy::RecordBalanceMismatch {
+                    account_name: history.account_name.clone(),
+                    date: record.date,
+                    amount: record.amount,
+                    reported,
+                    expected,
+                });
+            }
+            // continue from the reported balance so that a single wrong
+            // row does not flag every older booking
+            expected = reported;
+        }
+        expected -= record.amount;
+    }
+
+    discrepancies
+}
+
+fn balance_mismatches(histories: &[&AccountHistory]) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+
+    for (a, b) in histories.iter().tuple_combinations() {
+        let (older, newer) = if a.current_balance_date <= b.current_balance_date {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        if let Some(reconstructed) = balance_at_end_of(newer, older.current_balance_date) {
+            if (reconstructed - older.current_balance).abs() > TOLERANCE {
+                discrepancies.push(Discrepancy::BalanceMismatch {
+                    account_name: older.account_name.clone(),
+                    date: older.current_balance_date,
+                    reported: older.current_balance,
+                    reconstructed,
+                    reconstructed_from: newer.current_balance_date,
+                });
+            }
+        }
+    }
+
+    discrepancies
+}
+
+fn gaps(histories: &[&AccountHistory]) -> Vec<Discrepancy> {
+    let coverages: Vec<(NaiveDate, NaiveDate)> = histories
+        .iter()
+        .filter_map(|h| h.covered_period().map(|period| (period.from, period.to)))
+        .sorted()
+        .collect();
+
+    let mut discrepancies = Vec::new();
+    let mut covered_until: Option<NaiveDate> = None;
+
+    for (start, end) in coverages {
+        if let Some(until) = covered_until {
+            if start > until + Duration::days(1) {
+                discrepancies.push(Discrepancy::Gap {
+                    account_name: histories[0].account_name.clone(),
+                    from: until + Duration::days(1),
+                    to: start - Duration::days(1),
+                });
+            }
+        }
+        covered_until = Some(covered_until.map_or(end, |until| until.max(end)));
+    }
+
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::*;
+    use super::*;
+    use crate::model::{account_history::StatementPeriod, AccountRecord};
+
+    fn history(
+        balance_date: &str,
+        balance: f64,
+        period: (&str, &str),
+        records: Vec<AccountRecord>,
+    ) -> AccountHistory {
+        AccountHistory {
+            current_balance_date: str_date(balance_date),
+            current_balance: balance,
+            period: Some(StatementPeriod {
+                from: str_date(period.0),
+                to: str_date(period.1),
+            }),
+            records,
+            ..given_a_history()
+        }
+    }
+
+    fn record_with_balance(amount: f64, date: &str, balance: f64) -> AccountRecord {
+        AccountRecord {
+            balance: Some(balance),
+            ..new_record(amount, date)
+        }
+    }
+
+    #[test]
+    fn consistent_overlapping_statements_have_no_discrepancies() {
+        let older = history(
+            "6.3.2024",
+            350.0,
+            ("1.3.2024", "6.3.2024"),
+            vec![new_record(300.0, "5.3.2024"), new_record(-50.0, "3.3.2024")],
+        );
+        let newer = history(
+            "10.3.2024",
+            330.0,
+            ("4.3.2024", "10.3.2024"),
+            vec![new_record(-20.0, "8.3.2024"), new_record(300.0, "5.3.2024")],
+        );
+
+        assert_eq!(validate(&[older, newer]), vec![]);
+    }
+
+    #[test]
+    fn balances_contradicting_other_statements_are_reported() {
+        let older = history(
+            "6.3.2024",
+            350.0,
+            ("1.3.2024", "6.3.2024"),
+            vec![new_record(300.0, "5.3.2024")],
+        );
+        let newer = history(
+            "10.3.2024",
+            400.0,
+            ("4.3.2024", "10.3.2024"),
+            vec![new_record(-20.0, "8.3.2024"), new_record(300.0, "5.3.2024")],
+        );
+
+        assert_eq!(
+            validate(&[older, newer]),
+            vec![Discrepancy::BalanceMismatch {
+                account_name: "1018793511".to_string(),
+                date: str_date("6.3.2024"),
+                reported: 350.0,
+                reconstructed: 420.0,
+                reconstructed_from: str_date("10.3.2024"),
+            }]
+        );
+    }
+
+    #[test]
+    fn days_not_covered_by_any_statement_are_reported() {
+        let older = history("31.1.2024", 100.0, ("1.1.2024", "31.1.2024"), vec![]);
+        let newer = history("31.3.2024", 100.0, ("1.3.2024", "31.3.2024"), vec![]);
+
+        assert_eq!(
+            validate(&[newer, older]),
+            vec![Discrepancy::Gap {
+                account_name: "1018793511".to_string(),
+                from: str_date("1.2.2024"),
+                to: str_date("29.2.2024"),
+            }]
+        );
+    }
+
+    #[test]
+    fn wrong_running_balances_of_records_are_reported() {
+        let statement = history(
+            "6.3.2024",
+            350.0,
+            ("1.3.2024", "6.3.2024"),
+            vec![
+                record_with_balance(300.0, "5.3.2024", 350.0),
+                record_with_balance(-50.0, "3.3.2024", 60.0),
+                record_with_balance(100.0, "1.3.2024", 110.0),
+            ],
+        );
+
+        assert_eq!(
+            validate(&[statement]),
+            vec![Discrepancy::RecordBalanceMismatch {
+                account_name: "1018793511".to_string(),
+                date: str_date("3.3.2024"),
+                amount: -50.0,
+                reported: 60.0,
+                expected: 50.0,
+            }]
+        );
+    }
+}
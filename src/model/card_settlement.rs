@@ -0,0 +1,362 @@
+use std::fmt::Display;
+
+use chrono::{Duration, NaiveDate};
+use itertools::Itertools;
+
+use super::account_history::{AccountHistory, StatementPeriod};
+use super::{AccountRecord, AccountType};
+
+/// Amounts closer than this are considered equal.
+const TOLERANCE: f64 = 0.005;
+
+/// Length of the billing period before the first settlement of an account.
+const FIRST_BILLING_PERIOD_DAYS: i64 = 31;
+
+/// Words in the other side, booking text or purpose which mark the
+/// booking settling a credit card, on the giro as well as on the card.
+const SETTLEMENT_MARKERS: [&str; 4] = [
+    "visa-card",
+    "kreditkartenabrechnung",
+    "kreditkartenausgleich",
+    "ausgleichsbuchung",
+];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SettlementStatus {
+    /// The settlement equals the card bookings of the billing period.
+    Matched,
+    /// The settlement differs from the card bookings, e.g. because of fees
+    /// which are not part of the card statement.
+    AmountMismatch,
+    /// No card statement covers the billing period.
+    MissingCardStatement,
+}
+
+/// A booking on a giro account which settles the credit card.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CardSettlement {
+    pub account_name: String,
+    pub record: AccountRecord,
+    /// From the day after the previous settlement to this settlement.
+    pub billing_period: StatementPeriod,
+    /// The sum of the card bookings within the billing period.
+    pub card_total: f64,
+    pub status: SettlementStatus,
+}
+
+impl CardSettlement {
+    pub fn difference(&self) -> f64 {
+        self.record.amount - self.card_total
+    }
+
+    /// Whether the card bookings replace the settlement, which is then
+    /// only a transfer between own accounts apart from its `difference`.
+    pub fn is_internal_transfer(&self) -> bool {
+        self.status != SettlementStatus::MissingCardStatement
+    }
+}
+
+impl Display for CardSettlement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: settlement of {:.2} on {} for {} to {}: ",
+            self.account_name,
+            self.record.amount,
+            self.record.date,
+            self.billing_period.from,
+            self.billing_period.to
+        )?;
+        match self.status {
+            SettlementStatus::Matched => write!(f, "matches the card bookings"),
+            SettlementStatus::AmountMismatch => write!(
+                f,
+                "card bookings sum up to {:.2}, difference {:.2}",
+                self.card_total,
+                self.difference()
+            ),
+            SettlementStatus::MissingCardStatement => write!(f, "no card statement found"),
+        }
+    }
+}
+
+pub fn is_settlement(record: &AccountRecord) -> bool {
+    let texts = [
+        record.other_side.as_deref(),
+        Some(record.booking_text.as_str()),
+        record.purpose.as_deref(),
+    ];
+    texts.iter().flatten().any(|text| {
+        let text = text.to_lowercase();
+        SETTLEMENT_MARKERS
+            .iter()
+            .any(|marker| text.contains(marker))
+    })
+}
+
+fn is_card(history: &AccountHistory) -> bool {
+    history.account_type == AccountType::DKBCreditCard
+}
+
+/// Identifies a booking contained in several overlapping exports.
+fn booking_key(record: &AccountRecord) -> String {
+    record.id.clone().unwrap_or_else(|| record.identity())
+}
+
+/// Matches every settlement on a giro account with the sum of the card
+/// bookings since the previous settlement of the same account. The
+/// bookings of all card statements are combined, as the settlements do not
+/// name the card.
+pub fn reconcile_card_settlements(histories: &[AccountHistory]) -> Vec<CardSettlement> {
+    let cards: Vec<&AccountHistory> = histories.iter().filter(|h| is_card(h)).collect();
+    let card_records: Vec<&AccountRecord> = cards
+        .iter()
+        .flat_map(|h| h.records.iter())
+        .filter(|r| !r.is_pending() && !is_settlement(r))
+        .unique_by(|r| booking_key(r))
+        .collect();
+    let covered = |period: &StatementPeriod| {
+        cards.iter().any(|h| {
+            h.covered_period()
+                .is_some_and(|c| c.from <= period.from && period.to <= c.to)
+        })
+    };
+
+    // overlapping exports of the same account contain the same settlements
+    let accounts = histories
+        .iter()
+        .filter(|h| !is_card(h))
+        .into_group_map_by(|h| h.account_name.clone());
+
+    let mut settlements = Vec::new();
+    for (account_name, exports) in accounts.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        let records: Vec<&AccountRecord> = exports
+            .iter()
+            .flat_map(|h| h.records.iter())
+            .filter(|r| is_settlement(r))
+            .unique_by(|r| booking_key(r))
+            .sorted_by_key(|r| r.date)
+            .collect();
+
+        let mut previous: Option<NaiveDate> = None;
+        for record in records {
+            let billing_period = StatementPeriod {
+                from: match previous {
+                    Some(date) => date + Duration::days(1),
+                    None => record.date - Duration::days(FIRST_BILLING_PERIOD_DAYS),
+                },
+                to: record.date,
+            };
+            previous = Some(record.date);
+
+            let card_total = card_records
+                .iter()
+                .filter(|r| billing_period.from <= r.date && r.date <= billing_period.to)
+                .fold(0.0, |sum, r| sum + r.amount);
+            let status = if !covered(&billing_period) {
+                SettlementStatus::MissingCardStatement
+            } else if (record.amount - card_total).abs() <= TOLERANCE {
+                SettlementStatus::Matched
+            } else {
+                SettlementStatus::AmountMismatch
+            };
+
+            settlements.push(CardSettlement {
+                account_name: account_name.clone(),
+                record: record.clone(),
+                billing_period,
+                card_total,
+                status,
+            });
+        }
+    }
+
+    settlements
+}
+
+/// Removes the settlements which are transfers between own accounts and
+/// their counterparts on the card statements. Of a settlement differing
+/// from the card bookings only the difference remains, e.g. a fee.
+pub fn remove_settlements(
+    histories: &[AccountHistory],
+    settlements: &[CardSettlement],
+) -> Vec<AccountHistory> {
+    histories
+        .iter()
+        .map(|h| AccountHistory {
+            records: h
+                .records
+                .iter()
+                .filter_map(|r| {
+                    if is_card(h) {
+                        return (!is_settlement(r)).then(|| r.clone());
+                    }
+
+                    let settlement = settlements.iter().find(|s| {
+                        s.is_internal_transfer()
+                            && s.account_name == h.account_name
+                            && s.record == *r
+                    });
+                    match settlement {
+                        None => Some(r.clone()),
+                        Some(s) if s.difference().abs() > TOLERANCE => Some(AccountRecord {
+                            amount: s.difference(),
+                            balance: None,
+                            ..r.clone()
+                        }),
+                        Some(_) => None,
+                    }
+                })
+                .collect(),
+            ..h.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::*;
+    use super::*;
+
+    fn history(
+        account_type: AccountType,
+        from: &str,
+        to: &str,
+        records: Vec<AccountRecord>,
+    ) -> AccountHistory {
+        AccountHistory {
+            account_name: format!("{account_type:?}"),
+            account_type,
+            current_balance_date: str_date(to),
+            current_balance: 0.0,
+            period: Some(StatementPeriod {
+                from: str_date(from),
+                to: str_date(to),
+            }),
+            records,
+            positions: vec![],
+        }
+    }
+
+    fn settlement(amount: f64, date: &str) -> AccountRecord {
+        AccountRecord {
+            booking_text: "Umbuchung".to_string(),
+            purpose: Some("VISA-CARD 4930********0595 Ausgleichsbuchung".to_string()),
+            ..new_record(amount, date)
+        }
+    }
+
+    #[test]
+    fn settlements_are_matched_with_the_card_bookings() {
+        let giro = history(
+            AccountType::DKBAccount,
+            "1.1.2024",
+            "30.4.2024",
+            vec![
+                settlement(-124.0, "20.4.2024"),
+                new_record(-30.0, "5.4.2024"),
+                settlement(-80.5, "20.3.2024"),
+                settlement(-60.0, "20.2.2024"),
+            ],
+        );
+        let card = history(
+            AccountType::DKBCreditCard,
+            "1.2.2024",
+            "30.4.2024",
+            vec![
+                new_record(-100.0, "18.4.2024"),
+                new_record(-20.0, "2.4.2024"),
+                settlement(80.5, "21.3.2024"),
+                new_record(-80.5, "1.3.2024"),
+            ],
+        );
+        let histories = vec![giro, card];
+
+        let settlements = reconcile_card_settlements(&histories);
+
+        let statuses: Vec<(f64, SettlementStatus)> = settlements
+            .iter()
+            .map(|s| (s.card_total, s.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (0.0, SettlementStatus::MissingCardStatement),
+                (-80.5, SettlementStatus::Matched),
+                (-120.0, SettlementStatus::AmountMismatch),
+            ]
+        );
+        assert_eq!(settlements[2].billing_period.from, str_date("21.3.2024"));
+        assert!((settlements[2].difference() + 4.0).abs() < TOLERANCE);
+
+        let remaining = remove_settlements(&histories, &settlements);
+
+        assert_eq!(
+            remaining[0].records,
+            vec![
+                AccountRecord {
+                    amount: -4.0,
+                    ..settlement(-124.0, "20.4.2024")
+                },
+                new_record(-30.0, "5.4.2024"),
+                settlement(-60.0, "20.2.2024")
+            ]
+        );
+        assert_eq!(remaining[1].records.len(), 3);
+    }
+
+    #[test]
+    fn overlapping_statements_of_an_account_are_combined() {
+        let older = history(
+            AccountType::DKBAccount,
+            "1.1.2024",
+            "31.3.2024",
+            vec![
+                settlement(-80.5, "20.3.2024"),
+                settlement(-60.0, "20.2.2024"),
+            ],
+        );
+        let newer = history(
+            AccountType::DKBAccount,
+            "1.3.2024",
+            "30.4.2024",
+            vec![
+                settlement(-120.0, "20.4.2024"),
+                settlement(-80.5, "20.3.2024"),
+            ],
+        );
+        let card = history(
+            AccountType::DKBCreditCard,
+            "1.1.2024",
+            "30.4.2024",
+            vec![
+                new_record(-100.0, "18.4.2024"),
+                new_record(-20.0, "2.4.2024"),
+                new_record(-80.5, "1.3.2024"),
+                new_record(-60.0, "1.2.2024"),
+            ],
+        );
+        let histories = vec![older, newer, card];
+
+        let settlements = reconcile_card_settlements(&histories);
+
+        let periods: Vec<(NaiveDate, SettlementStatus)> = settlements
+            .iter()
+            .map(|s| (s.billing_period.from, s.status))
+            .collect();
+        assert_eq!(
+            periods,
+            vec![
+                (str_date("20.1.2024"), SettlementStatus::Matched),
+                (str_date("21.2.2024"), SettlementStatus::Matched),
+                (str_date("21.3.2024"), SettlementStatus::Matched),
+            ]
+        );
+
+        let remaining = remove_settlements(&histories, &settlements);
+
+        assert!(remaining[0].records.is_empty());
+        assert!(remaining[1].records.is_empty());
+        assert_eq!(remaining[2].records.len(), 4);
+    }
+}
@@ -0,0 +1,100 @@
+use super::AccountRecord;
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CategoryRule {
+    pub category: String,
+    pub other_side_contains: Option<String>,
+    pub booking_text_contains: Option<String>,
+    pub purpose_contains: Option<String>,
+}
+
+impl CategoryRule {
+    pub fn applies(&self, record: &AccountRecord) -> bool {
+        // a rule without any condition would match every record
+        if self.other_side_contains.is_none()
+            && self.booking_text_contains.is_none()
+            && self.purpose_contains.is_none()
+        {
+            return false;
+        }
+
+        contains_if_required(&self.other_side_contains, record.other_side.as_deref())
+            && contains_if_required(&self.booking_text_contains, Some(&record.booking_text))
+            && contains_if_required(&self.purpose_contains, record.purpose.as_deref())
+    }
+}
+
+pub(super) fn contains_if_required(required: &Option<String>, actual: Option<&str>) -> bool {
+    match (required, actual) {
+        (None, _) => true,
+        (Some(required), Some(actual)) => actual.to_lowercase().contains(&required.to_lowercase()),
+        (Some(_), None) => false,
+    }
+}
+
+/// Returns the category set on the record, or the category of the first
+/// rule that applies to it.
+pub fn categorize<'a>(record: &'a AccountRecord, rules: &'a [CategoryRule]) -> Option<&'a str> {
+    if let Some(category) = record.category.as_deref() {
+        return Some(category);
+    }
+
+    rules
+        .iter()
+        .find(|rule| rule.applies(record))
+        .map(|rule| rule.category.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        category: &str,
+        other_side_contains: Option<&str>,
+        purpose_contains: Option<&str>,
+    ) -> CategoryRule {
+        CategoryRule {
+            category: category.to_string(),
+            other_side_contains: other_side_contains.map(|s| s.to_string()),
+            booking_text_contains: None,
+            purpose_contains: purpose_contains.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn the_first_applying_rule_determines_the_category() {
+        let rules = vec![
+            rule("Food", Some("edeka"), None),
+            rule("Child", None, Some("Kita")),
+            rule("Shopping", Some("EDEKA"), None),
+        ];
+
+        let record = AccountRecord {
+            other_side: Some("EDEKA.BERGER".to_string()),
+            ..AccountRecord::default()
+        };
+        assert_eq!(categorize(&record, &rules), Some("Food"));
+
+        let record = AccountRecord {
+            other_side: Some("Stadt".to_string()),
+            purpose: Some("Beitrag KITA Mai".to_string()),
+            ..AccountRecord::default()
+        };
+        assert_eq!(categorize(&record, &rules), Some("Child"));
+    }
+
+    #[test]
+    fn records_without_applying_rule_are_not_categorized() {
+        let rules = vec![rule("Food", Some("edeka"), None), rule("Empty", None, None)];
+
+        let record = AccountRecord {
+            other_side: None,
+            booking_text: "Auszahlung".to_string(),
+            ..AccountRecord::default()
+        };
+
+        assert_eq!(categorize(&record, &rules), None);
+    }
+}
@@ -0,0 +1,201 @@
+use itertools::Itertools;
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::AccountRecord;
+
+/// Words card terminals and banks add to the merchant name.
+const NOISE_WORDS: [&str; 8] = [
+    "VISA",
+    "DEBIT",
+    "DEBITK",
+    "GIROCARD",
+    "SAGT",
+    "DANKE",
+    "KARTENZAHLUNG",
+    "LASTSCHRIFT",
+];
+
+/// Legal forms, compared without dots.
+const LEGAL_FORMS: [&str; 12] = [
+    "GMBH", "AG", "SE", "KG", "OHG", "EG", "SA", "RL", "SARL", "BV", "LTD", "INC",
+];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CounterpartyNormalizationError {
+    #[error("Invalid alias pattern {0}.")]
+    InvalidPattern(String),
+}
+
+/// Maps all counterparties matching the regular expression `pattern` to
+/// the canonical `name`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct CounterpartyAlias {
+    pub pattern: String,
+    pub name: String,
+}
+
+pub struct CounterpartyNormalizer {
+    aliases: Vec<(Regex, String)>,
+}
+
+impl CounterpartyNormalizer {
+    pub fn new(
+        aliases: &[CounterpartyAlias],
+    ) -> Result<CounterpartyNormalizer, CounterpartyNormalizationError> {
+        let aliases = aliases
+            .iter()
+            .map(|alias| {
+                RegexBuilder::new(&alias.pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map(|regex| (regex, alias.name.clone()))
+                    .map_err(|_| {
+                        CounterpartyNormalizationError::InvalidPattern(alias.pattern.clone())
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CounterpartyNormalizer { aliases })
+    }
+
+    /// Returns the name of the first matching alias, otherwise the cleaned
+    /// up name given by the bank. Names consisting only of noise have no
+    /// canonical name.
+    pub fn canonical_name(&self, record: &AccountRecord) -> Option<String> {
+        let raw = record.raw_counterparty();
+
+        match self.aliases.iter().find(|(regex, _)| regex.is_match(raw)) {
+            Some((_, name)) => Some(name.clone()),
+            None => clean_counterparty(raw),
+        }
+    }
+
+    /// Sets the canonical counterparty of all records. Records without a
+    /// canonical name keep the name given by the bank.
+    pub fn apply(&self, records: &mut [AccountRecord]) {
+        for record in records.iter_mut() {
+            record.canonical_counterparty = self.canonical_name(record);
+        }
+    }
+}
+
+/// Removes terminal columns, reference numbers, card names and legal forms
+/// from a counterparty name, e.g. "EDEKA BERGER SAGT DANKE" and
+/// "EDEKA.BERGER" both become "Edeka Berger". Returns `None` if nothing
+/// but noise remains.
+pub fn clean_counterparty(raw: &str) -> Option<String> {
+    // card terminals separate the city from the merchant by a column of spaces
+    let merchant = raw.trim().split("  ").next().unwrap_or_default();
+
+    let cleaned = merchant
+        .split_whitespace()
+        .filter(|token| !token.contains('*') && !token.chars().any(|c| c.is_ascii_digit()))
+        .filter(|token| !LEGAL_FORMS.contains(&token.replace('.', "").to_uppercase().as_str()))
+        .flat_map(|token| token.split(['.', '/', ',']))
+        .filter(|word| !word.is_empty())
+        .filter(|word| !NOISE_WORDS.contains(&word.to_uppercase().as_str()))
+        .map(title_case)
+        .join(" ");
+
+    if cleaned.is_empty() {
+        None
+    } else {
+        Some(cleaned)
+    }
+}
+
+fn title_case(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(|c| c.to_lowercase()))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn card_terminal_noise_is_removed() {
+        assert_eq!(
+            clean_counterparty("EDEKA.BERGER").as_deref(),
+            Some("Edeka Berger")
+        );
+        assert_eq!(
+            clean_counterparty("EDEKA BERGER SAGT DANKE").as_deref(),
+            Some("Edeka Berger")
+        );
+        assert_eq!(
+            clean_counterparty("VISA AMZN MKTP DE*CB3UF2VD5").as_deref(),
+            Some("Amzn Mktp")
+        );
+        assert_eq!(
+            clean_counterparty("AMAZON EU S.A R.L.").as_deref(),
+            Some("Amazon Eu")
+        );
+        assert_eq!(
+            clean_counterparty("ROSSMANN 1234      MUENCHEN").as_deref(),
+            Some("Rossmann")
+        );
+        assert_eq!(clean_counterparty("Debitk.63 VISA Debit"), None);
+    }
+
+    #[test]
+    fn aliases_take_precedence_over_the_cleanup() {
+        let normalizer = CounterpartyNormalizer::new(&[CounterpartyAlias {
+            pattern: "amzn|amazon".to_string(),
+            name: "Amazon".to_string(),
+        }])
+        .unwrap();
+
+        let mut records = vec![
+            AccountRecord {
+                other_side: Some("VISA AMZN MKTP DE*CB3UF2VD5".to_string()),
+                ..AccountRecord::default()
+            },
+            AccountRecord {
+                other_side: None,
+                booking_text: "AMAZON EU S.A R.L.".to_string(),
+                ..AccountRecord::default()
+            },
+            AccountRecord {
+                other_side: Some("EDEKA.BERGER".to_string()),
+                ..AccountRecord::default()
+            },
+            AccountRecord {
+                other_side: Some("Debitk.63 VISA Debit".to_string()),
+                ..AccountRecord::default()
+            },
+        ];
+
+        normalizer.apply(&mut records);
+
+        let names: Vec<&str> = records.iter().map(|r| r.counterparty()).collect();
+        assert_eq!(
+            names,
+            vec!["Amazon", "Amazon", "Edeka Berger", "Debitk.63 VISA Debit"]
+        );
+        assert_eq!(records[3].canonical_counterparty, None);
+    }
+
+    #[test]
+    fn invalid_alias_patterns_are_rejected() {
+        let result = CounterpartyNormalizer::new(&[CounterpartyAlias {
+            pattern: "(".to_string(),
+            name: "Broken".to_string(),
+        }]);
+
+        assert_eq!(
+            result.err(),
+            Some(CounterpartyNormalizationError::InvalidPattern(
+                "(".to_string()
+            ))
+        );
+    }
+}
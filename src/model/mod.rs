@@ -1,13 +1,24 @@
 pub mod account_history;
+pub mod balance_validation;
+pub mod card_settlement;
+pub mod categorization;
+pub mod overrides;
+pub mod portfolio;
+pub mod counterparty_normalization;
 pub mod year_month;
 pub mod monthly_report;
 pub mod monthly_reports;
+pub mod period_reports;
+pub mod record_filter;
 pub mod record_merging;
+pub mod refund_matching;
 
 use core::hash::Hasher;
-use std::hash::Hash;
+use std::{fmt::Display, hash::Hash};
 
 use chrono::NaiveDate;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub enum RecordCategory {
@@ -17,21 +28,67 @@ pub enum RecordCategory {
     Saving,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum AccountType {
     DKBAccount,
     DKBCreditCard,
     IngGiroAccount,
     IngExtraAccount,
+    IngDepot,
+    DkbDepot,
 }
 
-#[derive(Debug, Default, PartialEq, Clone)]
+impl AccountType {
+    /// Depots hold securities, their balance is the market value.
+    pub fn is_depot(&self) -> bool {
+        matches!(self, AccountType::IngDepot | AccountType::DkbDepot)
+    }
+}
+
+/// Whether a booking is settled and contained in the reported balance.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum BookingStatus {
+    #[default]
+    Booked,
+    /// Pre-authorized, it may still change or disappear.
+    Pending,
+    /// Settled, but not contained in the balance of the statement, e.g.
+    /// credit card bookings which were already billed.
+    NotInBalance,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AccountRecord {
     pub amount: f64,
     pub date: NaiveDate,
     pub other_side: Option<String>,
     pub booking_text: String,
     pub purpose: Option<String>,
+    /// The account balance after this booking, if the statement reports it.
+    pub balance: Option<f64>,
+    /// The cleaned up name of the other side, see `counterparty_normalization`.
+    pub canonical_counterparty: Option<String>,
+    /// The currency of the payment, if it was not made in euros.
+    #[serde(default)]
+    pub original_currency: Option<String>,
+    #[serde(default)]
+    pub status: BookingStatus,
+    /// Set manually, takes precedence over the category rules.
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Set manually to filter and group reports.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Identifies the booking across imports, see
+    /// `AccountHistory::assign_transaction_ids`.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// The name of the account of the booking, see
+    /// `AccountHistory::name_records`.
+    #[serde(default)]
+    pub account_name: Option<String>,
 }
 
 impl AccountRecord {
@@ -42,6 +99,95 @@ impl AccountRecord {
     pub fn is_spending(&self) -> bool {
         self.amount < 0.0
     }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == BookingStatus::Pending
+    }
+
+    /// The contents which identify a booking, equal for the same booking in
+    /// overlapping exports. The booking text is left out because it differs
+    /// between the export formats of a bank, e.g. the old DKB export names
+    /// a transfer "UMBUCHUNG" and the current one "Eingang". Bookings which
+    /// only differ in their booking text therefore share an identity and
+    /// are numbered by their order within a statement, see
+    /// `AccountHistory::assign_transaction_ids`.
+    pub fn identity(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.date,
+            (self.amount * 100.0).round() as i64,
+            self.raw_counterparty(),
+            self.purpose.as_deref().unwrap_or_default()
+        )
+    }
+
+    /// Derives the identifier of the booking from the account, its contents
+    /// and how many bookings with the same contents came before it.
+    pub fn transaction_id(&self, account_name: &str, occurrence: usize) -> String {
+        let contents = format!("{account_name}|{}|{occurrence}", self.identity());
+        format!("{:016x}", fnv_1a(contents.as_bytes()))
+    }
+
+    /// Whether the booking is contained in the balance of its statement.
+    pub fn counts_in_balance(&self) -> bool {
+        self.status == BookingStatus::Booked
+    }
+
+    /// The other side of the booking as given by the bank. Credit card
+    /// statements only name the merchant in the booking text.
+    pub fn raw_counterparty(&self) -> &str {
+        match self.other_side.as_deref() {
+            Some(other_side) if !other_side.trim().is_empty() => other_side,
+            _ => &self.booking_text,
+        }
+    }
+
+    /// The canonical name of the other side if it has been normalized,
+    /// otherwise the name given by the bank.
+    pub fn counterparty(&self) -> &str {
+        match self.canonical_counterparty.as_deref() {
+            Some(name) => name,
+            None => self.raw_counterparty(),
+        }
+    }
+}
+
+impl Display for AccountRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self.purpose.as_deref() {
+            Some(purpose) if !purpose.trim().is_empty() => purpose,
+            _ => &self.booking_text,
+        };
+        if let Some(id) = self.id.as_deref() {
+            write!(f, "{id}  ")?;
+        }
+        write!(
+            f,
+            "{} {:>10.2}  {}  {}",
+            self.date,
+            self.amount,
+            self.counterparty(),
+            description.split_whitespace().join(" ")
+        )?;
+        if let Some(note) = self.note.as_deref() {
+            write!(f, "  ({note})")?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, "  [{}]", self.tags.join(", "))?;
+        }
+        if self.is_pending() {
+            write!(f, "  (pending)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The 64 bit FNV-1a hash, which unlike `DefaultHasher` is guaranteed to
+/// stay the same across Rust versions.
+fn fnv_1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
 }
 
 impl Eq for AccountRecord {}
@@ -56,7 +202,7 @@ impl Hash for AccountRecord {
 }
 
 #[cfg(test)]
-mod test_util {
+pub mod test_util {
     use super::*;
     use crate::model::account_history::AccountHistory;
 
@@ -66,11 +212,13 @@ mod test_util {
             account_type: AccountType::DKBAccount,
             current_balance_date: str_date("6.3.2024"),
             current_balance: 350.0,
+            period: None,
             records: vec![
                 new_record(300.0, "5.3.2024"),
                 new_record(-50.0, "3.3.2024"),
                 new_record(100.0, "1.3.2024"),
             ],
+            positions: vec![],
         }
     }
 
@@ -85,6 +233,13 @@ mod test_util {
         }
     }
 
+    pub fn new_record_with_other_side(amount: f64, date: &str, other_side: &str) -> AccountRecord {
+        AccountRecord {
+            other_side: Some(other_side.to_string()),
+            ..new_record(amount, date)
+        }
+    }
+
     pub fn str_date(date: &str) -> NaiveDate {
         chrono::NaiveDate::parse_from_str(date, "%d.%m.%Y").unwrap()
     }
@@ -15,16 +15,16 @@ impl Display for MonthlyReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let n = 10;
 
-        write!(f, "{}\n", self.month)?;
-        write!(f, "Earnings: {}\n", self.earnings())?;
+        writeln!(f, "{}", self.month)?;
+        writeln!(f, "Earnings: {}", self.earnings())?;
         for r in self.biggest_earnings(n) {
-            write!(f, "  {:?}\n", r)?;
+            writeln!(f, "  {}", r)?;
         }
-        write!(f, "Spendings: {}\n", self.spendings())?;
+        writeln!(f, "Spendings: {}", self.spendings())?;
         for r in self.biggest_spendings(n) {
-            write!(f, "  {:?}\n", r)?;
+            writeln!(f, "  {}", r)?;
         }
-        write!(f, "Balance: {}\n", self.balance())
+        writeln!(f, "Balance: {}", self.balance())
     }
 }
 
@@ -47,20 +47,20 @@ impl MonthlyReport {
             .collect()
     }
 
+    /// Months without bookings are reported as well, so the sums start at
+    /// 0.0: summing up no floats results in -0.0, printed as "-0.00".
     pub fn earnings(&self) -> f64 {
         self.records
             .iter()
             .filter(|r| r.is_earning())
-            .map(|r| r.amount)
-            .sum()
+            .fold(0.0, |sum, r| sum + r.amount)
     }
 
     pub fn spendings(&self) -> f64 {
         self.records
             .iter()
             .filter(|r| r.is_spending())
-            .map(|r| r.amount)
-            .sum()
+            .fold(0.0, |sum, r| sum + r.amount)
     }
 
     pub fn balance(&self) -> f64 {
@@ -91,7 +91,7 @@ mod tests {
 
         let result = report.biggest_earnings(2);
 
-        let expected = vec![new_record(130.0, "1.1.2024"), new_record(120.0, "1.1.2024")];
+        let expected = [new_record(130.0, "1.1.2024"), new_record(120.0, "1.1.2024")];
 
         assert_eq!(result, expected.iter().collect::<Vec<&AccountRecord>>());
     }
@@ -113,11 +113,25 @@ mod tests {
 
         let result = report.biggest_spendings(2);
 
-        let expected = vec![
+        let expected = [
             new_record(-130.0, "1.1.2024"),
             new_record(-120.0, "1.1.2024"),
         ];
 
         assert_eq!(result, expected.iter().collect::<Vec<&AccountRecord>>());
     }
+
+    #[test]
+    fn months_without_bookings_sum_up_to_positive_zero() {
+        let report = MonthlyReport {
+            month: YearMonth {
+                year: 2024,
+                month0: 0,
+            },
+            records: vec![],
+        };
+
+        assert_eq!(format!("{:.2}", report.earnings()), "0.00");
+        assert_eq!(format!("{:.2}", report.spendings()), "0.00");
+    }
 }
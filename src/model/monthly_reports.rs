@@ -1,20 +1,99 @@
 use std::collections::HashMap;
 
 use chrono::Datelike;
+use itertools::Itertools;
 
+use super::account_history::StatementPeriod;
 use super::monthly_report::MonthlyReport;
 use super::year_month::YearMonth;
 use super::AccountRecord;
 
+/// Share of the smallest and of the biggest values ignored by the trimmed
+/// mean.
+pub const TRIMMED_SHARE: f64 = 0.2;
+
 #[derive(Default)]
 pub struct MonthlyReports {
     pub reports: Vec<MonthlyReport>,
+    /// Months removed by `exclude_incomplete_months`.
+    pub excluded_months: Vec<YearMonth>,
+}
+
+/// Statistics over the totals of several months.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct MonthlyStatistics {
+    pub mean: f64,
+    pub median: f64,
+    pub trimmed_mean: f64,
+    pub standard_deviation: f64,
 }
 
+impl MonthlyStatistics {
+    pub fn of(values: &[f64]) -> MonthlyStatistics {
+        MonthlyStatistics {
+            mean: average(values),
+            median: median(values),
+            trimmed_mean: trimmed_mean(values, TRIMMED_SHARE),
+            standard_deviation: standard_deviation(values),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RollingAverage {
+    /// The last month of the window.
+    pub month: YearMonth,
+    pub earnings: f64,
+    pub spendings: f64,
+}
+
+/// Returns 0 for an empty slice.
 pub fn average(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
     values.iter().sum::<f64>() / (values.len() as f64)
 }
 
+fn sorted(values: &[f64]) -> Vec<f64> {
+    values
+        .iter()
+        .cloned()
+        .sorted_by(|a, b| a.partial_cmp(b).unwrap())
+        .collect()
+}
+
+/// Returns 0 for an empty slice.
+pub fn median(values: &[f64]) -> f64 {
+    let sorted = sorted(values);
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => 0.0,
+        n if n.is_multiple_of(2) => (sorted[mid - 1] + sorted[mid]) / 2.0,
+        _ => sorted[mid],
+    }
+}
+
+/// The mean after dropping the given share of the smallest and of the
+/// biggest values.
+pub fn trimmed_mean(values: &[f64], share: f64) -> f64 {
+    let sorted = sorted(values);
+    let trimmed = (sorted.len() as f64 * share).floor() as usize;
+    average(&sorted[trimmed..sorted.len() - trimmed])
+}
+
+/// The population standard deviation, 0 for an empty slice.
+pub fn standard_deviation(values: &[f64]) -> f64 {
+    let mean = average(values);
+    average(
+        &values
+            .iter()
+            .map(|v| (v - mean).powi(2))
+            .collect::<Vec<f64>>(),
+    )
+    .sqrt()
+}
+
 impl MonthlyReports {
     pub fn create(records: Vec<AccountRecord>) -> MonthlyReports {
         let mut records_by_month: HashMap<YearMonth, Vec<AccountRecord>> = HashMap::new();
@@ -26,12 +105,17 @@ impl MonthlyReports {
                 month0: record.date.month0(),
             };
 
-            if !records_by_month.contains_key(&year_month) {
-                let v: Vec<AccountRecord> = Vec::new();
-                records_by_month.insert(year_month.clone(), v);
-            }
+            records_by_month.entry(year_month).or_default().push(record);
+        }
 
-            records_by_month.get_mut(&year_month).unwrap().push(record);
+        // Report months without any records as well
+        if let (Some(first), Some(last)) = (
+            records_by_month.keys().min().copied(),
+            records_by_month.keys().max().copied(),
+        ) {
+            for month in YearMonth::range(first, last) {
+                records_by_month.entry(month).or_default();
+            }
         }
 
         // Convert into report
@@ -43,24 +127,83 @@ impl MonthlyReports {
 
         reports.sort_unstable_by(|a, b| a.month.compare(&b.month));
 
-        MonthlyReports { reports }
+        MonthlyReports {
+            reports,
+            excluded_months: vec![],
+        }
+    }
+
+    /// Removes the reports of months which are not completely covered by
+    /// the statements of every account active in that month, like the
+    /// current month, and remembers them in `excluded_months`. Accounts
+    /// whose statements start after or end before a month do not count
+    /// for it.
+    pub fn exclude_incomplete_months(&mut self, covered_periods: &[StatementPeriod]) {
+        let (complete, incomplete): (Vec<MonthlyReport>, Vec<MonthlyReport>) =
+            self.reports.drain(..).partition(|r| {
+                let (first_day, last_day) = (r.month.first_day(), r.month.last_day());
+                let mut active = covered_periods
+                    .iter()
+                    .filter(|period| period.from <= last_day && period.to >= first_day)
+                    .peekable();
+                active.peek().is_some()
+                    && active.all(|period| first_day >= period.from && last_day <= period.to)
+            });
+
+        self.reports = complete;
+        self.excluded_months
+            .extend(incomplete.into_iter().map(|r| r.month));
+        self.excluded_months.sort();
+    }
+
+    pub fn earnings(&self) -> Vec<f64> {
+        self.reports.iter().map(|r| r.earnings()).collect()
+    }
+
+    pub fn spendings(&self) -> Vec<f64> {
+        self.reports.iter().map(|r| r.spendings()).collect()
+    }
+
+    pub fn earnings_statistics(&self) -> MonthlyStatistics {
+        MonthlyStatistics::of(&self.earnings())
+    }
+
+    pub fn spendings_statistics(&self) -> MonthlyStatistics {
+        MonthlyStatistics::of(&self.spendings())
+    }
+
+    /// Averages over windows of `n` consecutive reports, one for every
+    /// report from the `n`th on.
+    pub fn rolling_averages(&self, n: usize) -> Vec<RollingAverage> {
+        if n == 0 {
+            return vec![];
+        }
+
+        let earnings = self.earnings();
+        let spendings = self.spendings();
+
+        (n..=self.reports.len())
+            .map(|end| RollingAverage {
+                month: self.reports[end - 1].month,
+                earnings: average(&earnings[end - n..end]),
+                spendings: average(&spendings[end - n..end]),
+            })
+            .collect()
     }
 
     pub fn average_earnings(&self) -> f64 {
-        let earnings: Vec<f64> = self.reports.iter().map(|r| r.earnings()).collect();
-        average(&earnings)
+        average(&self.earnings())
     }
 
     pub fn average_spendings(&self) -> f64 {
-        let spendings: Vec<f64> = self.reports.iter().map(|r| r.spendings()).collect();
-        average(&spendings)
+        average(&self.spendings())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{super::test_util::*, *};
     use approx::assert_relative_eq;
-    use super::{super::test_util::*, MonthlyReport, MonthlyReports, YearMonth};
 
     #[test]
     fn return_average_spendings_and_earnings() {
@@ -87,6 +230,7 @@ mod tests {
                     ],
                 },
             ],
+            ..MonthlyReports::default()
         };
 
         let average_earnings = reports.average_earnings();
@@ -96,7 +240,6 @@ mod tests {
         assert_relative_eq!(average_spendings, -350.0);
     }
 
-
     #[test]
     fn create_monthly_reports() {
         let given_records_from_various_months = vec![
@@ -111,9 +254,10 @@ mod tests {
 
         let monthly_reports = MonthlyReports::create(given_records_from_various_months);
 
-        assert!(monthly_reports.reports.len() == 3);
+        assert!(monthly_reports.reports.len() == 13);
 
-        let expected_reports = vec![
+        assert_eq!(
+            monthly_reports.reports[0],
             MonthlyReport {
                 month: YearMonth::new(2023, 2),
                 records: vec![
@@ -121,30 +265,154 @@ mod tests {
                     new_record(200.0, "5.3.2023"),
                     new_record(-300.0, "6.3.2023"),
                 ],
-            },
+            }
+        );
+        assert_eq!(
+            monthly_reports.reports[1],
             MonthlyReport {
                 month: YearMonth::new(2023, 3),
                 records: vec![
                     new_record(400.0, "6.4.2023"),
                     new_record(200.0, "20.4.2023"),
                 ],
-            },
+            }
+        );
+        assert_eq!(
+            monthly_reports.reports[2],
+            MonthlyReport {
+                month: YearMonth::new(2023, 4),
+                records: vec![],
+            }
+        );
+        assert_eq!(
+            monthly_reports.reports[12],
             MonthlyReport {
                 month: YearMonth::new(2024, 2),
                 records: vec![
                     new_record(-300.0, "5.3.2024"),
                     new_record(400.0, "28.3.2024"),
                 ],
-            },
-        ];
-
-        assert_eq!(monthly_reports.reports, expected_reports);
+            }
+        );
         assert_relative_eq!(monthly_reports.reports[0].earnings(), 200.0);
         assert_relative_eq!(monthly_reports.reports[0].spendings(), -400.0);
         assert_relative_eq!(monthly_reports.reports[0].balance(), -200.0);
         assert_relative_eq!(monthly_reports.reports[1].earnings(), 600.0);
         assert_relative_eq!(monthly_reports.reports[1].spendings(), 0.0);
-        assert_relative_eq!(monthly_reports.reports[2].earnings(), 400.0);
-        assert_relative_eq!(monthly_reports.reports[2].spendings(), -300.0);
+        assert_relative_eq!(monthly_reports.reports[2].earnings(), 0.0);
+        assert_relative_eq!(monthly_reports.reports[12].earnings(), 400.0);
+        assert_relative_eq!(monthly_reports.reports[12].spendings(), -300.0);
+    }
+
+    fn report(month0: u32, earnings: f64, spendings: f64) -> MonthlyReport {
+        let date = format!("1.{}.2024", month0 + 1);
+        MonthlyReport {
+            month: YearMonth::new(2024, month0),
+            records: vec![new_record(earnings, &date), new_record(spendings, &date)],
+        }
+    }
+
+    #[test]
+    fn averages_of_no_reports_are_zero() {
+        let reports = MonthlyReports::default();
+
+        assert_relative_eq!(reports.average_earnings(), 0.0);
+        assert_eq!(reports.spendings_statistics(), MonthlyStatistics::default());
+        assert_eq!(reports.rolling_averages(3), vec![]);
+    }
+
+    #[test]
+    fn robust_statistics_are_not_skewed_by_single_outliers() {
+        let reports = MonthlyReports {
+            reports: vec![
+                report(0, 1000.0, -500.0),
+                report(1, 1000.0, -600.0),
+                report(2, 1000.0, -3400.0),
+                report(3, 1000.0, -500.0),
+                report(4, 6000.0, -500.0),
+            ],
+            ..MonthlyReports::default()
+        };
+
+        let earnings = reports.earnings_statistics();
+        assert_relative_eq!(earnings.mean, 2000.0);
+        assert_relative_eq!(earnings.median, 1000.0);
+        assert_relative_eq!(earnings.trimmed_mean, 1000.0);
+        assert_relative_eq!(earnings.standard_deviation, 2000.0);
+
+        let spendings = reports.spendings_statistics();
+        assert_relative_eq!(spendings.median, -500.0);
+        assert_relative_eq!(spendings.trimmed_mean, -1600.0 / 3.0);
+
+        let rolling = reports.rolling_averages(3);
+        assert_eq!(rolling.len(), 3);
+        assert_eq!(rolling[0].month, YearMonth::new(2024, 2));
+        assert_relative_eq!(rolling[0].spendings, -1500.0);
+        assert_relative_eq!(rolling[2].earnings, 8000.0 / 3.0);
+    }
+
+    #[test]
+    fn months_before_the_first_statement_of_an_account_are_complete() {
+        let mut reports = MonthlyReports {
+            reports: vec![
+                report(0, 100.0, -50.0),
+                report(1, 100.0, -50.0),
+                report(2, 100.0, -50.0),
+                report(3, 100.0, -50.0),
+            ],
+            ..MonthlyReports::default()
+        };
+
+        reports.exclude_incomplete_months(&[
+            StatementPeriod {
+                from: str_date("1.1.2024"),
+                to: str_date("30.4.2024"),
+            },
+            StatementPeriod {
+                from: str_date("10.2.2024"),
+                to: str_date("31.3.2024"),
+            },
+        ]);
+
+        let months: Vec<YearMonth> = reports.reports.iter().map(|r| r.month).collect();
+        assert_eq!(
+            months,
+            vec![
+                YearMonth::new(2024, 0),
+                YearMonth::new(2024, 2),
+                YearMonth::new(2024, 3)
+            ]
+        );
+        assert_eq!(reports.excluded_months, vec![YearMonth::new(2024, 1)]);
+    }
+
+    #[test]
+    fn incomplete_months_are_excluded() {
+        let mut reports = MonthlyReports {
+            reports: vec![
+                report(0, 100.0, -50.0),
+                report(1, 100.0, -50.0),
+                report(2, 100.0, -50.0),
+            ],
+            ..MonthlyReports::default()
+        };
+
+        reports.exclude_incomplete_months(&[
+            StatementPeriod {
+                from: str_date("15.1.2024"),
+                to: str_date("31.3.2024"),
+            },
+            StatementPeriod {
+                from: str_date("1.1.2024"),
+                to: str_date("15.3.2024"),
+            },
+        ]);
+
+        let months: Vec<YearMonth> = reports.reports.iter().map(|r| r.month).collect();
+        assert_eq!(months, vec![YearMonth::new(2024, 1)]);
+        assert_eq!(
+            reports.excluded_months,
+            vec![YearMonth::new(2024, 0), YearMonth::new(2024, 2)]
+        );
     }
 }
@@ -0,0 +1,311 @@
+use itertools::Itertools;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::categorization::contains_if_required;
+use super::AccountRecord;
+
+/// Differences below this are rounding, not a missing part of a split.
+const SPLIT_TOLERANCE: f64 = 0.005;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OverrideError {
+    #[error("The parts of the split booking {0} do not fit its amount.")]
+    InvalidSplit(String),
+}
+
+/// A part of a booking with its own category, e.g. the household goods on
+/// a supermarket receipt.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct SplitPart {
+    /// Negative for spendings, like the amount of the booking.
+    pub amount: f64,
+    pub category: String,
+    pub note: Option<String>,
+}
+
+/// Manual changes to the booking with the given `AccountRecord::id`, or
+/// to all bookings matching the conditions if no id is given.
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct TransactionOverride {
+    pub id: Option<String>,
+    pub other_side_contains: Option<String>,
+    pub booking_text_contains: Option<String>,
+    pub purpose_contains: Option<String>,
+    pub note: Option<String>,
+    /// Free-form tags like "vacation 2024", usable to filter and group
+    /// reports.
+    pub tags: Vec<String>,
+    /// Takes precedence over the category rules.
+    pub category: Option<String>,
+    /// Leaves the booking out of all reports, e.g. a reimbursed business
+    /// expense. The account balances still contain it.
+    pub exclude: bool,
+    pub splits: Vec<SplitPart>,
+}
+
+impl TransactionOverride {
+    pub fn applies(&self, record: &AccountRecord) -> bool {
+        if let Some(id) = self.id.as_deref() {
+            return record.id.as_deref() == Some(id);
+        }
+
+        // an override without any condition would change every record
+        if self.other_side_contains.is_none()
+            && self.booking_text_contains.is_none()
+            && self.purpose_contains.is_none()
+        {
+            return false;
+        }
+
+        contains_if_required(&self.other_side_contains, Some(record.raw_counterparty()))
+            && contains_if_required(&self.booking_text_contains, Some(&record.booking_text))
+            && contains_if_required(&self.purpose_contains, record.purpose.as_deref())
+    }
+}
+
+/// Applies all overrides in the given order, so that later notes and
+/// categories win while tags are collected. Excluded bookings are removed
+/// and split bookings are replaced by the parts of the first split.
+pub fn apply_overrides(
+    records: Vec<AccountRecord>,
+    overrides: &[TransactionOverride],
+) -> Result<Vec<AccountRecord>, OverrideError> {
+    if overrides.is_empty() {
+        return Ok(records);
+    }
+
+    records
+        .into_iter()
+        .map(|mut record| {
+            let applying: Vec<&TransactionOverride> =
+                overrides.iter().filter(|o| o.applies(&record)).collect();
+            if applying.iter().any(|o| o.exclude) {
+                return Ok(vec![]);
+            }
+
+            for o in applying.iter() {
+                if o.note.is_some() {
+                    record.note = o.note.clone();
+                }
+                if o.category.is_some() {
+                    record.category = o.category.clone();
+                }
+                for tag in o.tags.iter() {
+                    if !record.tags.contains(tag) {
+                        record.tags.push(tag.clone());
+                    }
+                }
+            }
+
+            match applying.iter().find(|o| !o.splits.is_empty()) {
+                Some(o) => split(&record, &o.splits),
+                None => Ok(vec![record]),
+            }
+        })
+        .flatten_ok()
+        .collect()
+}
+
+/// Replaces the booking by its parts. Whatever the parts do not cover
+/// remains as a part with the category of the booking, so that sums stay
+/// the same. The parts are numbered after the id of the booking, e.g.
+/// "4f1c2a9b7e3d5608-2".
+///
+/// Parts must have the sign of the booking and must not add up to more
+/// than its amount, otherwise the remainder would turn a spending into an
+/// earning or the other way round.
+fn split(record: &AccountRecord, parts: &[SplitPart]) -> Result<Vec<AccountRecord>, OverrideError> {
+    let total = parts.iter().map(|p| p.amount).sum::<f64>();
+    if parts.iter().any(|p| p.amount * record.amount < 0.0)
+        || total.abs() > record.amount.abs() + SPLIT_TOLERANCE
+    {
+        return Err(OverrideError::InvalidSplit(format!(
+            "{} {:.2} {}",
+            record.date,
+            record.amount,
+            record.counterparty()
+        )));
+    }
+
+    let mut records: Vec<AccountRecord> = parts
+        .iter()
+        .map(|part| AccountRecord {
+            amount: part.amount,
+            category: Some(part.category.clone()),
+            note: part.note.clone().or(record.note.clone()),
+            balance: None,
+            ..record.clone()
+        })
+        .collect();
+
+    let remainder = record.amount - total;
+    if remainder.abs() > SPLIT_TOLERANCE {
+        records.push(AccountRecord {
+            amount: remainder,
+            balance: None,
+            ..record.clone()
+        });
+    }
+
+    for (number, part) in records.iter_mut().enumerate() {
+        part.id = record
+            .id
+            .as_ref()
+            .map(|id| format!("{}-{}", id, number + 1));
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::super::test_util::*;
+    use super::*;
+    use crate::model::{
+        categorization::categorize, monthly_reports::MonthlyReports, AccountRecord,
+    };
+
+    fn receipt() -> AccountRecord {
+        AccountRecord {
+            other_side: Some("Edeka".to_string()),
+            id: Some("4f1c2a9b7e3d5608".to_string()),
+            ..new_record(-80.0, "5.3.2024")
+        }
+    }
+
+    fn part(amount: f64, category: &str) -> SplitPart {
+        SplitPart {
+            amount,
+            category: category.to_string(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn split_bookings_are_replaced_by_their_parts() {
+        let overrides = vec![TransactionOverride {
+            id: Some("4f1c2a9b7e3d5608".to_string()),
+            splits: vec![part(-50.0, "Food"), part(-20.0, "Household")],
+            ..TransactionOverride::default()
+        }];
+        let other = new_record(-10.0, "6.3.2024");
+
+        let records = apply_overrides(vec![receipt(), other.clone()], &overrides).unwrap();
+
+        let categories: Vec<(f64, Option<&str>)> = records
+            .iter()
+            .map(|r| (r.amount, categorize(r, &[])))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![
+                (-50.0, Some("Food")),
+                (-20.0, Some("Household")),
+                (-10.0, None),
+                (-10.0, None)
+            ]
+        );
+        assert_eq!(records[3], other);
+        let ids: Vec<Option<&str>> = records.iter().map(|r| r.id.as_deref()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                Some("4f1c2a9b7e3d5608-1"),
+                Some("4f1c2a9b7e3d5608-2"),
+                Some("4f1c2a9b7e3d5608-3"),
+                None
+            ]
+        );
+
+        let reports = MonthlyReports::create(records);
+        assert_relative_eq!(reports.reports[0].spendings(), -90.0);
+    }
+
+    #[test]
+    fn the_remainder_keeps_the_category_of_the_booking() {
+        let overrides = vec![TransactionOverride {
+            id: Some("4f1c2a9b7e3d5608".to_string()),
+            splits: vec![part(-50.0, "Food")],
+            ..TransactionOverride::default()
+        }];
+        let categorized = AccountRecord {
+            category: Some("Shopping".to_string()),
+            ..receipt()
+        };
+
+        let records = apply_overrides(vec![categorized], &overrides).unwrap();
+
+        let categories: Vec<(f64, Option<&str>)> = records
+            .iter()
+            .map(|r| (r.amount, categorize(r, &[])))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![(-50.0, Some("Food")), (-30.0, Some("Shopping"))]
+        );
+    }
+
+    #[test]
+    fn splits_not_fitting_the_amount_of_the_booking_are_rejected() {
+        let too_big = vec![part(-60.0, "Food"), part(-30.0, "Household")];
+        let wrong_sign = vec![part(-90.0, "Food"), part(10.0, "Deposit")];
+
+        for splits in [too_big, wrong_sign] {
+            let overrides = vec![TransactionOverride {
+                id: Some("4f1c2a9b7e3d5608".to_string()),
+                splits,
+                ..TransactionOverride::default()
+            }];
+
+            assert_eq!(
+                apply_overrides(vec![receipt()], &overrides),
+                Err(OverrideError::InvalidSplit(
+                    "2024-03-05 -80.00 Edeka".to_string()
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn notes_tags_categories_and_exclusions_are_applied() {
+        let overrides = vec![
+            TransactionOverride {
+                other_side_contains: Some("edeka".to_string()),
+                tags: vec!["vacation 2024".to_string()],
+                category: Some("Food".to_string()),
+                ..TransactionOverride::default()
+            },
+            TransactionOverride {
+                id: Some("4f1c2a9b7e3d5608".to_string()),
+                note: Some("Groceries for the trip".to_string()),
+                tags: vec!["vacation 2024".to_string(), "shared".to_string()],
+                ..TransactionOverride::default()
+            },
+            TransactionOverride {
+                purpose_contains: Some("travel expenses".to_string()),
+                exclude: true,
+                ..TransactionOverride::default()
+            },
+        ];
+        let reimbursed = AccountRecord {
+            purpose: Some("Travel expenses Hamburg".to_string()),
+            ..new_record(-120.0, "6.3.2024")
+        };
+
+        let records = apply_overrides(vec![receipt(), reimbursed], &overrides).unwrap();
+
+        assert_eq!(
+            records,
+            vec![AccountRecord {
+                note: Some("Groceries for the trip".to_string()),
+                tags: vec!["vacation 2024".to_string(), "shared".to_string()],
+                category: Some("Food".to_string()),
+                ..receipt()
+            }]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use itertools::Itertools;
+
+use super::categorization::{categorize, CategoryRule};
+use super::monthly_reports::MonthlyReports;
+use super::year_month::YearMonth;
+use super::AccountRecord;
+
+/// Category of records which no category rule applies to.
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Granularity {
+    Month,
+    Quarter,
+    Year,
+    /// A year starting with the given month, e.g. 3 for April.
+    FiscalYear {
+        first_month0: u32,
+    },
+}
+
+impl Granularity {
+    fn length(&self) -> i32 {
+        match self {
+            Granularity::Month => 1,
+            Granularity::Quarter => 3,
+            Granularity::Year | Granularity::FiscalYear { .. } => 12,
+        }
+    }
+
+    /// Returns the period which contains the month.
+    pub fn period_of(&self, month: YearMonth) -> Period {
+        let first_month0 = match self {
+            Granularity::Month => month.month0,
+            Granularity::Quarter => month.month0 / 3 * 3,
+            Granularity::Year => 0,
+            Granularity::FiscalYear { first_month0 } => *first_month0,
+        };
+
+        let mut first = YearMonth::new(month.year, first_month0);
+        if first > month {
+            first = first.minus_months(12);
+        }
+
+        Period {
+            first,
+            last: first.plus_months(self.length() - 1),
+        }
+    }
+}
+
+/// A range of months, both inclusive.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Period {
+    pub first: YearMonth,
+    pub last: YearMonth,
+}
+
+impl Period {
+    pub fn contains(&self, month: YearMonth) -> bool {
+        self.first <= month && month <= self.last
+    }
+
+    /// The same period one year earlier.
+    pub fn previous_year(&self) -> Period {
+        Period {
+            first: self.first.minus_months(12),
+            last: self.last.minus_months(12),
+        }
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.first == self.last {
+            write!(f, "{}", self.first)
+        } else {
+            write!(f, "{} to {}", self.first, self.last)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PeriodTotals {
+    pub earnings: f64,
+    pub spendings: f64,
+    /// The sum of all bookings per category.
+    pub categories: BTreeMap<String, f64>,
+    /// The sum of all bookings per tag, bookings with several tags count
+    /// for each of them.
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl PeriodTotals {
+    pub fn of<'a>(
+        records: impl Iterator<Item = &'a AccountRecord>,
+        category_rules: &[CategoryRule],
+    ) -> PeriodTotals {
+        let mut totals = PeriodTotals::default();
+
+        for record in records {
+            if record.is_earning() {
+                totals.earnings += record.amount;
+            } else {
+                totals.spendings += record.amount;
+            }
+
+            if !category_rules.is_empty() {
+                let category = categorize(record, category_rules).unwrap_or(UNCATEGORIZED);
+                *totals.categories.entry(category.to_string()).or_default() += record.amount;
+            }
+
+            for tag in record.tags.iter() {
+                *totals.tags.entry(tag.clone()).or_default() += record.amount;
+            }
+        }
+
+        totals
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.earnings + self.spendings
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PeriodReport {
+    pub period: Period,
+    pub totals: PeriodTotals,
+}
+
+/// Sums up the monthly reports per period. Periods at the start and end
+/// may only be partially covered by the reports.
+pub fn period_reports(
+    reports: &MonthlyReports,
+    granularity: Granularity,
+    category_rules: &[CategoryRule],
+) -> Vec<PeriodReport> {
+    reports
+        .reports
+        .iter()
+        .chunk_by(|r| granularity.period_of(r.month))
+        .into_iter()
+        .map(|(period, reports)| PeriodReport {
+            period,
+            totals: PeriodTotals::of(reports.flat_map(|r| r.records.iter()), category_rules),
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Delta {
+    pub absolute: f64,
+    /// The change relative to the previous value, unknown if the previous
+    /// value is zero. Growing spendings have a positive percentage.
+    pub percentage: Option<f64>,
+}
+
+impl Delta {
+    pub fn between(previous: f64, current: f64) -> Delta {
+        Delta {
+            absolute: current - previous,
+            percentage: if previous == 0.0 {
+                None
+            } else {
+                Some((current - previous) / previous * 100.0)
+            },
+        }
+    }
+}
+
+impl Display for Delta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:+.2}", self.absolute)?;
+        match self.percentage {
+            Some(percentage) => write!(f, " ({percentage:+.1} %)"),
+            None => write!(f, " (n/a)"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PeriodComparison {
+    pub current: PeriodReport,
+    pub previous: PeriodReport,
+}
+
+impl PeriodComparison {
+    pub fn earnings(&self) -> Delta {
+        Delta::between(self.previous.totals.earnings, self.current.totals.earnings)
+    }
+
+    pub fn spendings(&self) -> Delta {
+        Delta::between(
+            self.previous.totals.spendings,
+            self.current.totals.spendings,
+        )
+    }
+
+    pub fn balance(&self) -> Delta {
+        Delta::between(
+            self.previous.totals.balance(),
+            self.current.totals.balance(),
+        )
+    }
+
+    /// Deltas of all categories occurring in either period.
+    pub fn categories(&self) -> Vec<(String, Delta)> {
+        let current = &self.current.totals.categories;
+        let previous = &self.previous.totals.categories;
+
+        current
+            .keys()
+            .chain(previous.keys())
+            .unique()
+            .sorted()
+            .map(|category| {
+                (
+                    category.clone(),
+                    Delta::between(
+                        *previous.get(category).unwrap_or(&0.0),
+                        *current.get(category).unwrap_or(&0.0),
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+fn report_for(
+    reports: &MonthlyReports,
+    period: Period,
+    category_rules: &[CategoryRule],
+) -> PeriodReport {
+    PeriodReport {
+        period,
+        totals: PeriodTotals::of(
+            reports
+                .reports
+                .iter()
+                .filter(|r| period.contains(r.month))
+                .flat_map(|r| r.records.iter()),
+            category_rules,
+        ),
+    }
+}
+
+pub fn compare_periods(
+    reports: &MonthlyReports,
+    current: Period,
+    previous: Period,
+    category_rules: &[CategoryRule],
+) -> PeriodComparison {
+    PeriodComparison {
+        current: report_for(reports, current, category_rules),
+        previous: report_for(reports, previous, category_rules),
+    }
+}
+
+/// Compares the month with the same month one year earlier.
+pub fn compare_with_last_year(
+    reports: &MonthlyReports,
+    month: YearMonth,
+    category_rules: &[CategoryRule],
+) -> PeriodComparison {
+    let current = Period {
+        first: month,
+        last: month,
+    };
+    compare_periods(reports, current, current.previous_year(), category_rules)
+}
+
+/// Compares the year up to and including the month with the same months
+/// one year earlier.
+pub fn compare_year_to_date(
+    reports: &MonthlyReports,
+    month: YearMonth,
+    category_rules: &[CategoryRule],
+) -> PeriodComparison {
+    let current = Period {
+        first: YearMonth::new(month.year, 0),
+        last: month,
+    };
+    compare_periods(reports, current, current.previous_year(), category_rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::super::test_util::*;
+    use super::*;
+
+    fn records() -> Vec<AccountRecord> {
+        vec![
+            new_record(1000.0, "1.1.2023"),
+            new_record(-200.0, "5.3.2023"),
+            AccountRecord {
+                purpose: Some("Kita".to_string()),
+                ..new_record(-100.0, "6.4.2023")
+            },
+            new_record(1200.0, "1.1.2024"),
+            new_record(-300.0, "5.3.2024"),
+            AccountRecord {
+                purpose: Some("Kita".to_string()),
+                ..new_record(-150.0, "6.3.2024")
+            },
+        ]
+    }
+
+    fn category_rules() -> Vec<CategoryRule> {
+        vec![CategoryRule {
+            category: "Child".to_string(),
+            other_side_contains: None,
+            booking_text_contains: None,
+            purpose_contains: Some("kita".to_string()),
+        }]
+    }
+
+    #[test]
+    fn periods_of_months_are_determined() {
+        let month = YearMonth::new(2024, 1);
+
+        assert_eq!(
+            Granularity::Quarter.period_of(month),
+            Period {
+                first: YearMonth::new(2024, 0),
+                last: YearMonth::new(2024, 2)
+            }
+        );
+        assert_eq!(
+            Granularity::FiscalYear { first_month0: 3 }.period_of(month),
+            Period {
+                first: YearMonth::new(2023, 3),
+                last: YearMonth::new(2024, 2)
+            }
+        );
+    }
+
+    #[test]
+    fn reports_are_summed_up_per_period() {
+        let reports = MonthlyReports::create(records());
+
+        let yearly = period_reports(&reports, Granularity::Year, &[]);
+        assert_eq!(yearly.len(), 2);
+        assert_relative_eq!(yearly[0].totals.earnings, 1000.0);
+        assert_relative_eq!(yearly[0].totals.spendings, -300.0);
+
+        let fiscal = period_reports(
+            &reports,
+            Granularity::FiscalYear { first_month0: 3 },
+            &category_rules(),
+        );
+        assert_eq!(fiscal.len(), 2);
+        assert_eq!(fiscal[1].period.first, YearMonth::new(2023, 3));
+        assert_relative_eq!(fiscal[1].totals.balance(), 650.0);
+        assert_relative_eq!(fiscal[1].totals.categories["Child"], -250.0);
+    }
+
+    #[test]
+    fn year_to_date_is_compared_with_last_year() {
+        let reports = MonthlyReports::create(records());
+
+        let comparison = compare_year_to_date(&reports, YearMonth::new(2024, 2), &category_rules());
+
+        assert_relative_eq!(comparison.earnings().absolute, 200.0);
+        assert_relative_eq!(comparison.earnings().percentage.unwrap(), 20.0);
+        assert_relative_eq!(comparison.spendings().absolute, -250.0);
+        assert_relative_eq!(comparison.spendings().percentage.unwrap(), 125.0);
+        assert_eq!(
+            comparison.categories(),
+            vec![
+                (
+                    "Child".to_string(),
+                    Delta {
+                        absolute: -150.0,
+                        percentage: None
+                    }
+                ),
+                (
+                    UNCATEGORIZED.to_string(),
+                    Delta {
+                        absolute: 100.0,
+                        percentage: Some(100.0 / 800.0 * 100.0)
+                    }
+                ),
+            ]
+        );
+
+        let monthly = compare_with_last_year(&reports, YearMonth::new(2024, 2), &[]);
+        assert_relative_eq!(monthly.spendings().absolute, -250.0);
+        assert!(monthly.categories().is_empty());
+    }
+}
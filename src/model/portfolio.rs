@@ -0,0 +1,130 @@
+use chrono::NaiveDate;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use super::account_history::AccountHistory;
+use super::AccountRecord;
+
+/// Words in the booking text or purpose of a cash account booking which
+/// mark the purchase or sale of securities.
+const SECURITIES_TRADE_MARKERS: [&str; 4] = [
+    "wertpapierkauf",
+    "wertpapierverkauf",
+    "wertpapierabrechnung",
+    "wp-abrechnung",
+];
+
+/// The holding of a single security in a depot.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub isin: String,
+    pub name: String,
+    pub quantity: f64,
+    /// What was paid for the quantity held, including fees.
+    pub cost_basis: f64,
+    /// The value at the valuation date of the holdings export.
+    pub market_value: f64,
+}
+
+impl Position {
+    pub fn gain(&self) -> f64 {
+        self.market_value - self.cost_basis
+    }
+}
+
+/// Whether the cash booking pays for or is paid by securities, which only
+/// moves money between the cash account and a depot.
+pub fn is_securities_trade(record: &AccountRecord) -> bool {
+    let texts = [
+        Some(record.booking_text.as_str()),
+        record.purpose.as_deref(),
+    ];
+    texts.iter().flatten().any(|text| {
+        let text = text.to_lowercase();
+        SECURITIES_TRADE_MARKERS
+            .iter()
+            .any(|marker| text.contains(marker))
+    })
+}
+
+/// The most recent holdings of every depot valued on or before the date.
+pub fn holdings_at(histories: &[AccountHistory], date: NaiveDate) -> Vec<&AccountHistory> {
+    histories
+        .iter()
+        .filter(|h| h.account_type.is_depot() && !h.positions.is_empty())
+        .filter(|h| h.current_balance_date <= date)
+        .into_group_map_by(|h| h.account_name.clone())
+        .into_values()
+        .filter_map(|h| h.into_iter().max_by_key(|h| h.current_balance_date))
+        .sorted_by(|a, b| a.account_name.cmp(&b.account_name))
+        .collect()
+}
+
+/// The market value of all depots according to their most recent holdings
+/// export on or before the date.
+pub fn depot_value_at(histories: &[AccountHistory], date: NaiveDate) -> f64 {
+    holdings_at(histories, date)
+        .iter()
+        .fold(0.0, |sum, h| sum + h.current_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::super::test_util::*;
+    use super::*;
+    use crate::model::AccountType;
+
+    fn holdings(account_name: &str, date: &str, market_values: &[f64]) -> AccountHistory {
+        let positions: Vec<Position> = market_values
+            .iter()
+            .map(|value| Position {
+                isin: "IE00B4L5Y983".to_string(),
+                name: "iShares Core MSCI World".to_string(),
+                quantity: 10.0,
+                cost_basis: 800.0,
+                market_value: *value,
+            })
+            .collect();
+        AccountHistory {
+            account_name: account_name.to_string(),
+            account_type: AccountType::IngDepot,
+            current_balance_date: str_date(date),
+            current_balance: market_values.iter().sum(),
+            period: None,
+            records: vec![],
+            positions,
+        }
+    }
+
+    #[test]
+    fn the_most_recent_holdings_determine_the_depot_value() {
+        let histories = vec![
+            holdings("ING", "30.6.2024", &[1000.0]),
+            holdings("ING", "31.8.2024", &[1100.0, 300.0]),
+            holdings("DKB", "31.7.2024", &[500.0]),
+            given_a_history(),
+        ];
+
+        assert_relative_eq!(depot_value_at(&histories, str_date("1.6.2024")), 0.0);
+        assert_relative_eq!(depot_value_at(&histories, str_date("31.7.2024")), 1500.0);
+        assert_relative_eq!(depot_value_at(&histories, str_date("1.9.2024")), 1900.0);
+        assert_relative_eq!(histories[1].positions[0].gain(), 300.0);
+    }
+
+    #[test]
+    fn securities_trades_are_recognized() {
+        let purchase = AccountRecord {
+            booking_text: "Wertpapierkauf".to_string(),
+            ..new_record(-500.0, "2.9.2024")
+        };
+        let dividend = AccountRecord {
+            booking_text: "Ertragsgutschrift".to_string(),
+            ..new_record(12.5, "2.9.2024")
+        };
+
+        assert!(is_securities_trade(&purchase));
+        assert!(!is_securities_trade(&dividend));
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::NaiveDate;
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+use super::categorization::{categorize, CategoryRule};
+use super::AccountRecord;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RecordFilterError {
+    #[error("Invalid text pattern {0}.")]
+    InvalidPattern(String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Sign {
+    Earning,
+    Spending,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RecordOrder {
+    Date,
+    Amount,
+    Counterparty,
+}
+
+/// Selects records by their properties. Conditions which are not set do
+/// not restrict the result.
+#[derive(Debug, Default, Clone)]
+pub struct RecordFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    /// Compared with the absolute amount, so that spendings above 100 are
+    /// found with a minimum of 100.
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub sign: Option<Sign>,
+    /// Compared with the account name of the records, see
+    /// `AccountHistory::name_records`.
+    pub account_name: Option<String>,
+    /// Matched against the other side, the booking text and the purpose.
+    pub text: Option<Regex>,
+    pub category: Option<String>,
+    /// One of the tags set in the overrides, compared case-insensitively.
+    pub tag: Option<String>,
+}
+
+impl RecordFilter {
+    /// Sets a case-insensitive text pattern.
+    pub fn with_text(self, pattern: &str) -> Result<RecordFilter, RecordFilterError> {
+        let text = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|_| RecordFilterError::InvalidPattern(pattern.to_string()))?;
+
+        Ok(RecordFilter {
+            text: Some(text),
+            ..self
+        })
+    }
+
+    pub fn matches(&self, record: &AccountRecord, category_rules: &[CategoryRule]) -> bool {
+        self.from.is_none_or(|from| record.date >= from)
+            && self.to.is_none_or(|to| record.date <= to)
+            && self.min_amount.is_none_or(|min| record.amount.abs() >= min)
+            && self.max_amount.is_none_or(|max| record.amount.abs() <= max)
+            && self.sign.is_none_or(|sign| match sign {
+                Sign::Earning => record.is_earning(),
+                Sign::Spending => record.is_spending(),
+            })
+            && self
+                .account_name
+                .as_deref()
+                .is_none_or(|name| record.account_name.as_deref() == Some(name))
+            && self
+                .text
+                .as_ref()
+                .is_none_or(|text| text_matches(text, record))
+            && self.category.as_deref().is_none_or(|category| {
+                categorize(record, category_rules)
+                    .is_some_and(|actual| actual.eq_ignore_ascii_case(category))
+            })
+            && self
+                .tag
+                .as_deref()
+                .is_none_or(|tag| record.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+    }
+
+    /// Returns the matching records in the given order.
+    pub fn apply<'a>(
+        &self,
+        records: &'a [AccountRecord],
+        category_rules: &[CategoryRule],
+        order: RecordOrder,
+        descending: bool,
+    ) -> Vec<&'a AccountRecord> {
+        let mut matches: Vec<&AccountRecord> = records
+            .iter()
+            .filter(|r| self.matches(r, category_rules))
+            .collect();
+
+        matches.sort_by(|a, b| {
+            let ordering = match order {
+                RecordOrder::Date => a.date.cmp(&b.date),
+                RecordOrder::Amount => a.amount.partial_cmp(&b.amount).unwrap(),
+                RecordOrder::Counterparty => a.counterparty().cmp(b.counterparty()),
+            };
+            ordering.then(a.date.cmp(&b.date))
+        });
+        if descending {
+            matches.reverse();
+        }
+
+        matches
+    }
+}
+
+fn text_matches(text: &Regex, record: &AccountRecord) -> bool {
+    [
+        record.other_side.as_deref(),
+        Some(record.booking_text.as_str()),
+        record.purpose.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|field| text.is_match(field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::*;
+    use super::*;
+
+    fn record(amount: f64, date: &str, other_side: &str, purpose: &str) -> AccountRecord {
+        AccountRecord {
+            other_side: Some(other_side.to_string()),
+            purpose: Some(purpose.to_string()),
+            ..new_record(amount, date)
+        }
+    }
+
+    fn records() -> Vec<AccountRecord> {
+        vec![
+            record(-150.0, "1.2.2024", "Stadt Muenchen", "Kita Gebuehr Februar"),
+            record(-80.0, "1.1.2024", "Stadt Muenchen", "KITA Essensgeld"),
+            record(
+                -150.0,
+                "1.12.2023",
+                "Stadt Muenchen",
+                "Kita Gebuehr Dezember",
+            ),
+            record(2000.0, "28.1.2024", "Employer", "Gehalt"),
+        ]
+    }
+
+    #[test]
+    fn all_conditions_must_match() {
+        let filter = RecordFilter {
+            from: Some(str_date("1.1.2024")),
+            to: Some(str_date("31.12.2024")),
+            min_amount: Some(100.0),
+            sign: Some(Sign::Spending),
+            ..RecordFilter::default()
+        }
+        .with_text("kita")
+        .unwrap();
+
+        let records = records();
+        let result = filter.apply(&records, &[], RecordOrder::Date, false);
+
+        assert_eq!(result, vec![&records[0]]);
+    }
+
+    #[test]
+    fn records_can_be_filtered_by_category_and_sorted() {
+        let rules = vec![CategoryRule {
+            category: "Child".to_string(),
+            other_side_contains: None,
+            booking_text_contains: None,
+            purpose_contains: Some("kita".to_string()),
+        }];
+        let filter = RecordFilter {
+            category: Some("child".to_string()),
+            ..RecordFilter::default()
+        };
+
+        let records = records();
+        let result = filter.apply(&records, &rules, RecordOrder::Amount, true);
+
+        assert_eq!(result, vec![&records[1], &records[0], &records[2]]);
+    }
+
+    #[test]
+    fn records_can_be_filtered_by_tag() {
+        let mut records = records();
+        records[2].tags = vec!["Vacation 2024".to_string()];
+        let filter = RecordFilter {
+            tag: Some("vacation 2024".to_string()),
+            ..RecordFilter::default()
+        };
+
+        let result = filter.apply(&records, &[], RecordOrder::Date, false);
+
+        assert_eq!(result, vec![&records[2]]);
+    }
+
+    #[test]
+    fn records_can_be_filtered_by_account() {
+        let mut records = records();
+        for (record, account_name) in records.iter_mut().zip(["Giro", "Giro", "Card", "Giro"]) {
+            record.account_name = Some(account_name.to_string());
+        }
+        let filter = RecordFilter {
+            account_name: Some("Card".to_string()),
+            ..RecordFilter::default()
+        };
+
+        let result = filter.apply(&records, &[], RecordOrder::Date, false);
+
+        assert_eq!(result, vec![&records[2]]);
+    }
+
+    #[test]
+    fn invalid_text_patterns_are_rejected() {
+        assert_eq!(
+            RecordFilter::default().with_text("(").err(),
+            Some(RecordFilterError::InvalidPattern("(".to_string()))
+        );
+    }
+}
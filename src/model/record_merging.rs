@@ -10,14 +10,14 @@ pub struct MergeRule {
 }
 
 impl MergeRule {
-    fn applies(&self, record: &AccountRecord) -> bool {
+    pub fn applies(&self, record: &AccountRecord) -> bool {
         self.other_side_rule_applies(record) && self.booking_text_rule_applies(record)
     }
 
     fn other_side_rule_applies(&self, record: &AccountRecord) -> bool {
         // contrary to the booking text rule, a rule value of None must
         // also be evaluated
-        if self.other_side_is == None && record.other_side == None {
+        if self.other_side_is.is_none() && record.other_side.is_none() {
             return true;
         }
 
@@ -54,7 +54,7 @@ pub fn merge_records(
         .into_iter()
         .filter(|record| !remove_rules.iter().any(|rule| rule.applies(record)))
         .collect();
-    return filtered_records;
+    filtered_records
 }
 
 pub fn merge_records_from_date(
@@ -88,11 +88,13 @@ mod tests {
             account_type: AccountType::DKBAccount,
             current_balance_date: str_date("6.3.2024"),
             current_balance: 350.0,
+            period: None,
             records: vec![
                 new_record(300.0, "5.3.2024"),
                 new_record(-50.0, "3.3.2024"),
                 new_record(100.0, "1.3.2024"),
             ],
+            positions: vec![],
         }
     }
 
@@ -0,0 +1,214 @@
+use std::fmt::Display;
+
+use chrono::Duration;
+
+use super::AccountRecord;
+
+/// Refunds are matched with purchases up to this many days before them.
+pub const REFUND_WINDOW_DAYS: i64 = 90;
+
+/// Differences below this are rounding, not a partial refund.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// Words in the booking text or purpose which mark a refund, a reversal or
+/// a chargeback. Only these may refund a purchase partially.
+const REFUND_MARKERS: [&str; 7] = [
+    "gutschrift",
+    "rücklastschrift",
+    "rückbuchung",
+    "erstattung",
+    "storno",
+    "refund",
+    "chargeback",
+];
+
+/// A refund and the purchase it (partially) reverses.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RefundMatch {
+    pub refund: AccountRecord,
+    pub purchase: AccountRecord,
+}
+
+impl Display for RefundMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}\n  refunds {}", self.refund, self.purchase)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RefundMatching {
+    /// The records with refunds netted against their purchases.
+    pub records: Vec<AccountRecord>,
+    pub matches: Vec<RefundMatch>,
+    /// Records which look like refunds, but no purchase was found for.
+    pub unmatched: Vec<AccountRecord>,
+}
+
+pub fn is_marked_as_refund(record: &AccountRecord) -> bool {
+    let texts = [
+        Some(record.booking_text.as_str()),
+        record.purpose.as_deref(),
+    ];
+    texts.iter().flatten().any(|text| {
+        let text = text.to_lowercase();
+        REFUND_MARKERS.iter().any(|marker| text.contains(marker))
+    })
+}
+
+/// Links earnings to earlier spendings of the same counterparty within the
+/// window: any earning of exactly the amount, or a refund of at most the
+/// remaining amount. Matched refunds are subtracted from their purchases,
+/// so that they reduce the spendings in the month and category of the
+/// purchase instead of counting as earnings. Fully refunded purchases are
+/// removed.
+pub fn net_refunds(records: Vec<AccountRecord>, window_days: i64) -> RefundMatching {
+    // the amounts of the spendings which have not been refunded yet
+    let mut remaining: Vec<f64> = records.iter().map(|r| -r.amount).collect();
+    let mut purchase_of: Vec<Option<usize>> = vec![None; records.len()];
+
+    let mut earnings: Vec<usize> = (0..records.len())
+        .filter(|i| records[*i].amount > 0.0)
+        .collect();
+    earnings.sort_by_key(|i| records[*i].date);
+
+    for i in earnings {
+        let refund = &records[i];
+        let counterparty = refund.counterparty().to_lowercase();
+        let earliest = refund.date - Duration::days(window_days);
+        let partial = is_marked_as_refund(refund);
+
+        let candidates: Vec<usize> = (0..records.len())
+            .filter(|j| {
+                let purchase = &records[*j];
+                purchase.is_spending()
+                    && purchase.date >= earliest
+                    && purchase.date <= refund.date
+                    && purchase.counterparty().to_lowercase() == counterparty
+                    && remaining[*j] > AMOUNT_TOLERANCE
+            })
+            .collect();
+        let latest = |candidates: &mut dyn Iterator<Item = &usize>| {
+            candidates.copied().max_by_key(|j| (records[*j].date, *j))
+        };
+
+        let exact = latest(
+            &mut candidates
+                .iter()
+                .filter(|j| (remaining[**j] - refund.amount).abs() <= AMOUNT_TOLERANCE),
+        );
+        let purchase = exact.or_else(|| {
+            if partial {
+                latest(
+                    &mut candidates
+                        .iter()
+                        .filter(|j| remaining[**j] >= refund.amount),
+                )
+            } else {
+                None
+            }
+        });
+
+        if let Some(j) = purchase {
+            remaining[j] -= refund.amount;
+            purchase_of[i] = Some(j);
+        }
+    }
+
+    let mut matching = RefundMatching::default();
+    for (i, record) in records.iter().enumerate() {
+        match purchase_of[i] {
+            Some(j) => matching.matches.push(RefundMatch {
+                refund: record.clone(),
+                purchase: records[j].clone(),
+            }),
+            None if record.amount > 0.0 && is_marked_as_refund(record) => {
+                matching.unmatched.push(record.clone())
+            }
+            None => {}
+        }
+    }
+
+    matching.records = records
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| purchase_of[*i].is_none())
+        .filter_map(|(j, record)| {
+            let refunded = -record.amount - remaining[j];
+            if !record.is_spending() || refunded <= AMOUNT_TOLERANCE {
+                Some(record)
+            } else if remaining[j] > AMOUNT_TOLERANCE {
+                Some(AccountRecord {
+                    amount: -remaining[j],
+                    balance: None,
+                    ..record
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    matching
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::super::test_util::*;
+    use super::*;
+    use crate::model::monthly_reports::MonthlyReports;
+
+    fn booking(amount: f64, date: &str, counterparty: &str, booking_text: &str) -> AccountRecord {
+        AccountRecord {
+            other_side: Some(counterparty.to_string()),
+            booking_text: booking_text.to_string(),
+            ..new_record(amount, date)
+        }
+    }
+
+    #[test]
+    fn refunds_reduce_the_spendings_of_their_purchase() {
+        let records = vec![
+            booking(-80.0, "28.2.2024", "Amazon", "Kartenzahlung"),
+            booking(-30.0, "2.3.2024", "Amazon", "Kartenzahlung"),
+            booking(-2000.0, "1.3.2024", "Landlord", "Dauerauftrag"),
+            booking(30.0, "5.3.2024", "Amazon", "Gutschrift"),
+            booking(20.0, "6.4.2024", "Amazon", "Erstattung"),
+        ];
+
+        let matching = net_refunds(records.clone(), REFUND_WINDOW_DAYS);
+
+        assert_eq!(matching.matches.len(), 2);
+        assert_eq!(matching.matches[0].purchase, records[1]);
+        assert_eq!(matching.matches[1].purchase, records[0]);
+        assert!(matching.unmatched.is_empty());
+
+        let amounts: Vec<f64> = matching.records.iter().map(|r| r.amount).collect();
+        assert_eq!(amounts, vec![-60.0, -2000.0]);
+
+        let reports = MonthlyReports::create(matching.records);
+        assert_relative_eq!(reports.reports[0].spendings(), -60.0);
+        assert_relative_eq!(reports.reports[1].earnings(), 0.0);
+    }
+
+    #[test]
+    fn refunds_without_purchase_are_reported() {
+        let records = vec![
+            booking(-50.0, "1.1.2024", "Zalando", "Kartenzahlung"),
+            booking(3000.0, "28.3.2024", "Employer", "Gehalt"),
+            booking(50.0, "15.4.2024", "Zalando", "Gutschrift"),
+            booking(45.0, "16.4.2024", "Otto", "Rücklastschrift"),
+            booking(10.0, "17.4.2024", "Otto", "Überweisung"),
+        ];
+
+        let matching = net_refunds(records.clone(), REFUND_WINDOW_DAYS);
+
+        assert!(matching.matches.is_empty());
+        assert_eq!(
+            matching.unmatched,
+            vec![records[2].clone(), records[3].clone()]
+        );
+        assert_eq!(matching.records, records);
+    }
+}
@@ -1,6 +1,15 @@
-use std::{cmp::Ordering, fmt::Display};
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+use chrono::{Datelike, Months, NaiveDate};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum YearMonthError {
+    #[error("Invalid month {0}, expected YYYY-MM.")]
+    InvalidFormat(String),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct YearMonth {
     pub year: i32,
     pub month0: u32,
@@ -12,14 +21,121 @@ impl Display for YearMonth {
     }
 }
 
+/// Parses months like "2024-09" or "2024-9".
+impl FromStr for YearMonth {
+    type Err = YearMonthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || YearMonthError::InvalidFormat(s.to_string());
+
+        let (year, month) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let year: i32 = year.parse().map_err(|_| invalid())?;
+        let month: u32 = month.parse().map_err(|_| invalid())?;
+        if !(1..=12).contains(&month) {
+            return Err(invalid());
+        }
+
+        Ok(YearMonth::new(year, month - 1))
+    }
+}
+
 impl YearMonth {
     pub fn new(year: i32, month0: u32) -> YearMonth {
         YearMonth { year, month0 }
     }
 
+    pub fn of(date: NaiveDate) -> YearMonth {
+        YearMonth::new(date.year(), date.month0())
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month0 + 1, 1).unwrap()
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        self.first_day() + Months::new(1) - chrono::Duration::days(1)
+    }
+
+    /// Adds the given number of months, which may be negative.
+    pub fn plus_months(&self, months: i32) -> YearMonth {
+        let index = self.index() + months;
+        YearMonth::new(index.div_euclid(12), index.rem_euclid(12) as u32)
+    }
+
+    pub fn minus_months(&self, months: i32) -> YearMonth {
+        self.plus_months(-months)
+    }
+
+    /// The month following this one.
+    pub fn next(&self) -> YearMonth {
+        self.plus_months(1)
+    }
+
+    /// The number of months from this month to the other one, negative if
+    /// the other month is earlier.
+    pub fn months_until(&self, other: &YearMonth) -> i32 {
+        other.index() - self.index()
+    }
+
+    /// Iterates over all months from `first` to `last`, both inclusive.
+    pub fn range(first: YearMonth, last: YearMonth) -> impl Iterator<Item = YearMonth> {
+        (0..=first.months_until(&last)).map(move |i| first.plus_months(i))
+    }
+
     pub fn compare(&self, other: &YearMonth) -> Ordering {
         let a = self.year * 100 + self.month0 as i32;
         let b = other.year * 100 + other.month0 as i32;
         a.cmp(&b)
     }
-}
\ No newline at end of file
+
+    fn index(&self) -> i32 {
+        self.year * 12 + self.month0 as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_can_be_added_and_subtracted() {
+        let month = YearMonth::new(2024, 10);
+
+        assert_eq!(month.plus_months(1), YearMonth::new(2024, 11));
+        assert_eq!(month.plus_months(2), YearMonth::new(2025, 0));
+        assert_eq!(month.minus_months(11), YearMonth::new(2023, 11));
+        assert_eq!(month.minus_months(24), YearMonth::new(2022, 10));
+        assert_eq!(month.months_until(&YearMonth::new(2025, 1)), 3);
+        assert_eq!(month.months_until(&YearMonth::new(2024, 0)), -10);
+    }
+
+    #[test]
+    fn ranges_contain_all_months() {
+        let months: Vec<YearMonth> =
+            YearMonth::range(YearMonth::new(2023, 10), YearMonth::new(2024, 1)).collect();
+
+        assert_eq!(
+            months,
+            vec![
+                YearMonth::new(2023, 10),
+                YearMonth::new(2023, 11),
+                YearMonth::new(2024, 0),
+                YearMonth::new(2024, 1),
+            ]
+        );
+        assert_eq!(
+            YearMonth::range(YearMonth::new(2024, 1), YearMonth::new(2024, 0)).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn months_are_parsed() {
+        assert_eq!("2024-09".parse(), Ok(YearMonth::new(2024, 8)));
+        assert_eq!("2024-12".parse(), Ok(YearMonth::new(2024, 11)));
+        assert_eq!(
+            "2024-13".parse::<YearMonth>(),
+            Err(YearMonthError::InvalidFormat("2024-13".to_string()))
+        );
+    }
+}
@@ -1,8 +1,6 @@
-use encoding_rs_io::DecodeReaderBytes;
-use crate::model::account_history::AccountHistory;
-use std::{self, io::{self, BufReader}};
+use crate::model::account_history::{AccountHistory, StatementPeriod};
+use std::{self, io};
 use regex;
-use std::io::Read;
 use io::BufRead;
 use std::str::FromStr;
 use chrono::NaiveDate;
@@ -14,17 +12,21 @@ pub struct BankStatementHeaderParser {
     pub account_number_regex: String,
     pub balance_amount_regex: String,
     pub balance_date_regex: String,
+    pub period_start_regex: String,
+    pub period_end_regex: String,
     pub account_type: AccountType,
 }
 
 impl BankStatementHeaderParser {
     pub fn parse(
         &self,
-        line_reader: &mut BufReader<DecodeReaderBytes<impl Read, Vec<u8>>>,
+        line_reader: &mut impl BufRead,
     ) -> Result<AccountHistory, ParserError> {
         let mut account_name = String::from_str("AccountNumber").unwrap();
         let mut current_balance = 0.0;
         let mut current_balance_date = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let mut period_start = None;
+        let mut period_end = None;
     
         for _i in 0..self.header_length {
             let mut buf = String::new();
@@ -38,22 +40,39 @@ impl BankStatementHeaderParser {
             let current_balance_date_regex =
                 regex::Regex::new(&self.balance_date_regex).unwrap();
             if let Some(captures) = current_balance_date_regex.captures(&buf) {
-                current_balance_date = parse_date(&captures["date"].to_string()).unwrap();
+                current_balance_date = parse_date(&captures["date"]).unwrap();
             }
     
             let current_balance_regex =
                 regex::Regex::new(&self.balance_amount_regex).unwrap();
             if let Some(captures) = current_balance_regex.captures(&buf) {
-                current_balance = (self.parse_amount)(&captures["amount"].to_string())?;
+                current_balance = (self.parse_amount)(&captures["amount"])?;
+            }
+
+            let period_start_regex = regex::Regex::new(&self.period_start_regex).unwrap();
+            if let Some(captures) = period_start_regex.captures(&buf) {
+                period_start = Some(parse_date(&captures["date"])?);
+            }
+
+            let period_end_regex = regex::Regex::new(&self.period_end_regex).unwrap();
+            if let Some(captures) = period_end_regex.captures(&buf) {
+                period_end = Some(parse_date(&captures["date"])?);
             }
         }
+
+        let period = match (period_start, period_end) {
+            (Some(from), Some(to)) => Some(StatementPeriod { from, to }),
+            _ => None,
+        };
     
         Ok(AccountHistory {
             account_name,
             account_type: self.account_type,
             current_balance_date,
             current_balance,
+            period,
             records: vec![],
+            positions: vec![],
         })
     }
 }
\ No newline at end of file
@@ -3,24 +3,15 @@ use csv;
 
 pub struct DkbAccountParser {}
 
-impl DkbAccountParser {
-    pub fn can_parse(file_path: &str) -> Result<bool, ParserError> {
-        let file = File::open(file_path).unwrap();
-
-        let mut decoder = DecodeReaderBytesBuilder::new()
-            .encoding(Some(WINDOWS_1252))
-            .build(file);
-
-        let mut buf = String::new();
-        decoder
-            .read_to_string(&mut buf)
-            .map_err(|_| ParserError::FileReadError)?;
+impl BankStatementParserImplementation for DkbAccountParser {
+    fn name(&self) -> &'static str {
+        "DKB Girokonto"
+    }
 
-        Ok(buf.contains("/ Girokonto"))
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[(r#"/ Girokonto";"#, 80), (r#""Kontonummer:";"#, 20)])
     }
-}
 
-impl BankStatementParserImplementation for DkbAccountParser {
     fn get_header_parser(&self) -> BankStatementHeaderParser {
         BankStatementHeaderParser {
             header_length: 6,
@@ -29,6 +20,8 @@ impl BankStatementParserImplementation for DkbAccountParser {
             balance_amount_regex: r#"(?P<amount>[+-]?[\d,.]+) EUR"#.to_string(),
             parse_amount: parse_float,
             balance_date_regex: r#"Kontostand vom (?P<date>[\d.]+)"#.to_string(),
+            period_start_regex: r#""Von:";"(?P<date>[\d.]+)""#.to_string(),
+            period_end_regex: r#""Bis:";"(?P<date>[\d.]+)""#.to_string(),
             account_type: AccountType::DKBAccount,
         }
     }
@@ -40,6 +33,15 @@ impl BankStatementParserImplementation for DkbAccountParser {
             other_side: Some(record[3].to_string()),
             booking_text: record[2].to_string(),
             purpose: Some(record[4].to_string()),
+            balance: None,
+            canonical_counterparty: None,
+            original_currency: None,
+            status: BookingStatus::Booked,
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
         })
     }
 }
@@ -47,7 +49,7 @@ impl BankStatementParserImplementation for DkbAccountParser {
 #[cfg(test)]
 mod tests {
     use crate::{
-        model::{AccountRecord, AccountType},
+        model::{account_history::StatementPeriod, AccountRecord, AccountType, BookingStatus},
         parsers::parser_factory::ParserFactory,
     };
     use approx::assert_relative_eq;
@@ -68,6 +70,15 @@ mod tests {
                 other_side: Some("VISA-CARD GELDANLAGE".to_string()),
                 booking_text: "UMBUCHUNG".to_string(),
                 purpose: Some("4930 0000 2699 0595 AUSGLEICHSBUCHUNG".to_string()),
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE08120300001234567890".to_string()),
             },
             AccountRecord {
                 amount: -60.01,
@@ -75,6 +86,15 @@ mod tests {
                 other_side: Some("EDEKA.BERGER".to_string()),
                 booking_text: "Kartenzahlung".to_string(),
                 purpose: Some("2024-08-31      Debitk.63 VISA Debit".to_string()),
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE08120300001234567890".to_string()),
             },
         ];
 
@@ -85,6 +105,13 @@ mod tests {
             parser_result.current_balance_date,
             NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
         );
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2021, 9, 4).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
         assert_eq!(parser_result.records, expected_records);
     }
 }
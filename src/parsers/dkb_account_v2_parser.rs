@@ -0,0 +1,112 @@
+use crate::parsers::*;
+use csv;
+
+/// Reads the Girokonto exports of the DKB banking introduced in 2023, which
+/// contain pending bookings marked by a status column.
+pub struct DkbAccountV2Parser {}
+
+impl BankStatementParserImplementation for DkbAccountV2Parser {
+    fn name(&self) -> &'static str {
+        "DKB Girokonto (2023)"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(
+            prefix,
+            &[
+                (r#""Buchungsdatum";"Wertstellung";"Status";"#, 80),
+                (r#""Girokonto";"#, 20),
+            ],
+        )
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 5,
+            account_number_regex: r#""Girokonto";"(?P<account>[A-Z\d]+)""#.to_string(),
+            balance_amount_regex: r#""Kontostand vom [\d.]+:";"(?P<amount>[+-]?[\d,.]+) €""#
+                .to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#"Kontostand vom (?P<date>[\d.]+):"#.to_string(),
+            period_start_regex: r#""Zeitraum:";"(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#""Zeitraum:";"[\d.]+ - (?P<date>[\d.]+)""#.to_string(),
+            account_type: AccountType::DKBAccount,
+        }
+    }
+
+    fn parse_record(&self, record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        let amount = parse_euro_amount(&record[8])?;
+        // the payer for earnings, the payee for spendings
+        let other_side = if amount < 0.0 { &record[4] } else { &record[3] };
+
+        Ok(AccountRecord {
+            amount,
+            date: parse_short_date(&record[0])?,
+            other_side: Some(other_side.to_string()),
+            booking_text: record[6].to_string(),
+            purpose: Some(record[5].to_string()),
+            balance: None,
+            canonical_counterparty: None,
+            original_currency: None,
+            status: parse_dkb_status(&record[2]),
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{account_history::StatementPeriod, AccountType, BookingStatus},
+        parsers::parser_factory::ParserFactory,
+    };
+    use approx::assert_relative_eq;
+    use chrono::NaiveDate;
+
+    const FILE_PATH: &str = "./src/parsers/testData/dkb_account_v2_statement.csv";
+
+    #[test]
+    fn pending_bookings_are_marked() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser.implementation.name(), "DKB Girokonto (2023)");
+        assert_eq!(parser_result.account_name, "DE08120300001234567890");
+        assert_eq!(parser_result.account_type, AccountType::DKBAccount);
+        assert_relative_eq!(parser_result.current_balance, 10123.45);
+        assert_eq!(
+            parser_result.current_balance_date,
+            NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
+        );
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
+
+        let records: Vec<(f64, &str, BookingStatus)> = parser_result
+            .records
+            .iter()
+            .map(|r| (r.amount, r.counterparty(), r.status))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (-42.5, "AMAZON EU S.A R.L.", BookingStatus::Pending),
+                (0.97, "VISA-CARD GELDANLAGE", BookingStatus::Booked),
+                (-60.01, "EDEKA.BERGER", BookingStatus::Booked),
+            ]
+        );
+        assert_eq!(
+            parser_result.records[2].date,
+            NaiveDate::from_ymd_opt(2024, 9, 2).unwrap()
+        );
+    }
+}
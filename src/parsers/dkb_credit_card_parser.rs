@@ -3,19 +3,15 @@ use csv;
 
 pub struct DkbCreditCardParser {}
 
-impl DkbCreditCardParser {
-    pub fn can_parse(file_path: &str) -> Result<bool, ParserError>
-    {
-        let mut decoder = get_decoded_file_reader(file_path);
-
-        let mut buf = String::new();
-        decoder.read_to_string(&mut buf).map_err(|_| ParserError::FileReadError)?;
+impl BankStatementParserImplementation for DkbCreditCardParser {
+    fn name(&self) -> &'static str {
+        "DKB Kreditkarte"
+    }
 
-        Ok(buf.contains(r#""Kreditkarte:";"#))
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[(r#""Kreditkarte:";"#, 80), (r#""Umsatz abgerechnet"#, 20)])
     }
-}
 
-impl BankStatementParserImplementation for DkbCreditCardParser {
     fn get_header_parser(&self) -> BankStatementHeaderParser {
         BankStatementHeaderParser {
             header_length: 7,
@@ -24,6 +20,8 @@ impl BankStatementParserImplementation for DkbCreditCardParser {
             balance_amount_regex: r#"(?P<amount>[+-]?[\d,.]+) EUR"#.to_string(),
             parse_amount: parse_std_float,
             balance_date_regex: r#""Datum:";"(?P<date>[\d.]+)""#.to_string(),
+            period_start_regex: r#""Von:";"(?P<date>[\d.]+)""#.to_string(),
+            period_end_regex: r#""Bis:";"(?P<date>[\d.]+)""#.to_string(),
             account_type: AccountType::DKBCreditCard,
         }
     }
@@ -35,13 +33,31 @@ impl BankStatementParserImplementation for DkbCreditCardParser {
             other_side: None,
             booking_text: record[3].to_string(),
             purpose: None,
+            balance: None,
+            canonical_counterparty: None,
+            original_currency: parse_original_currency(record.get(5).unwrap_or_default()),
+            status: parse_status(&record[0]),
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
         })
     }
 }
 
+/// Bookings which are already billed are not contained in the balance.
+fn parse_status(billed: &str) -> BookingStatus {
+    match billed {
+        "Ja" => BookingStatus::NotInBalance,
+        _ => BookingStatus::Booked,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{model::{AccountRecord, AccountType}, parsers::parser_factory::ParserFactory};
+    use crate::{
+        model::{account_history::StatementPeriod, AccountRecord, AccountType, BookingStatus}, parsers::parser_factory::ParserFactory};
     use approx::assert_relative_eq;
     use chrono::NaiveDate;
 
@@ -54,12 +70,37 @@ mod tests {
         let parser_result = parser.parse(FILE_PATH).unwrap();
 
         let expected_records = vec![
+            AccountRecord {
+                amount: -25.3,
+                date: chrono::NaiveDate::from_ymd_opt(2024, 9, 2).unwrap(),
+                other_side: None,
+                booking_text: "REWE".to_string(),
+                purpose: None,
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("4930********0595".to_string()),
+            },
             AccountRecord {
                 amount: 0.6,
                 date: chrono::NaiveDate::from_ymd_opt(2024, 1, 23).unwrap(),
                 other_side: None,
                 booking_text: "HabenzinsenZ 000000432 T 018   0000".to_string(),
                 purpose: None,
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::NotInBalance,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("4930********0595".to_string()),
             },
             AccountRecord {
                 amount: -2400.0,
@@ -67,6 +108,31 @@ mod tests {
                 other_side: None,
                 booking_text: "Auszahlung".to_string(),
                 purpose: None,
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::NotInBalance,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("4930********0595".to_string()),
+            },
+            AccountRecord {
+                amount: -11.12,
+                date: chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap(),
+                other_side: None,
+                booking_text: "AMAZON.COM".to_string(),
+                purpose: None,
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: Some("USD".to_string()),
+                status: BookingStatus::NotInBalance,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("4930********0595".to_string()),
             },
         ];
 
@@ -77,6 +143,13 @@ mod tests {
             parser_result.current_balance_date,
             NaiveDate::from_ymd_opt(2024, 9, 3).unwrap()
         );
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2021, 9, 4).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
         assert_eq!(parser_result.records, expected_records);
     }
 }
\ No newline at end of file
@@ -0,0 +1,118 @@
+use crate::parsers::*;
+use csv;
+
+/// Reads the credit card exports of the DKB banking introduced in 2023,
+/// which contain pending bookings marked by a status column.
+pub struct DkbCreditCardV2Parser {}
+
+impl BankStatementParserImplementation for DkbCreditCardV2Parser {
+    fn name(&self) -> &'static str {
+        "DKB Kreditkarte (2023)"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(
+            prefix,
+            &[
+                (r#""Belegdatum";"Wertstellung";"Status";"#, 80),
+                (r#""Karte";"#, 20),
+            ],
+        )
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 5,
+            account_number_regex: r#""Karte";"[^"]*";"(?P<account>[\d*]+)""#.to_string(),
+            balance_amount_regex: r#""Saldo vom [\d.]+:";"(?P<amount>[+-]?[\d,.]+) €""#.to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#"Saldo vom (?P<date>[\d.]+):"#.to_string(),
+            period_start_regex: r#""Zeitraum:";"(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#""Zeitraum:";"[\d.]+ - (?P<date>[\d.]+)""#.to_string(),
+            account_type: AccountType::DKBCreditCard,
+        }
+    }
+
+    fn parse_record(&self, record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        // pending bookings have no value date yet
+        let date = match &record[1] {
+            "" => &record[0],
+            value_date => value_date,
+        };
+
+        Ok(AccountRecord {
+            amount: parse_euro_amount(&record[5])?,
+            date: parse_short_date(date)?,
+            other_side: None,
+            booking_text: record[3].to_string(),
+            purpose: None,
+            balance: None,
+            canonical_counterparty: None,
+            original_currency: parse_original_currency(record.get(6).unwrap_or_default()),
+            status: parse_dkb_status(&record[2]),
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        model::{AccountType, BookingStatus},
+        parsers::parser_factory::ParserFactory,
+    };
+    use approx::assert_relative_eq;
+    use chrono::NaiveDate;
+
+    const FILE_PATH: &str = "./src/parsers/testData/dkb_credit_card_v2_statement.csv";
+
+    #[test]
+    fn pending_bookings_are_marked() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser.implementation.name(), "DKB Kreditkarte (2023)");
+        assert_eq!(parser_result.account_name, "4930********0595");
+        assert_eq!(parser_result.account_type, AccountType::DKBCreditCard);
+        assert_relative_eq!(parser_result.current_balance, -36.42);
+        assert_eq!(
+            parser_result.current_balance_date,
+            NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
+        );
+
+        let records: Vec<(f64, NaiveDate, BookingStatus)> = parser_result
+            .records
+            .iter()
+            .map(|r| (r.amount, r.date, r.status))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (
+                    -19.99,
+                    NaiveDate::from_ymd_opt(2024, 9, 3).unwrap(),
+                    BookingStatus::Pending
+                ),
+                (
+                    -25.3,
+                    NaiveDate::from_ymd_opt(2024, 9, 2).unwrap(),
+                    BookingStatus::Booked
+                ),
+                (
+                    -11.12,
+                    NaiveDate::from_ymd_opt(2024, 8, 20).unwrap(),
+                    BookingStatus::Booked
+                ),
+            ]
+        );
+        assert_eq!(
+            parser_result.records[2].original_currency,
+            Some("USD".to_string())
+        );
+    }
+}
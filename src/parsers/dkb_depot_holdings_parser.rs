@@ -0,0 +1,67 @@
+use crate::parsers::*;
+use csv;
+
+pub struct DkbDepotHoldingsParser {}
+
+impl BankStatementParserImplementation for DkbDepotHoldingsParser {
+    fn name(&self) -> &'static str {
+        "DKB Depotbestand"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[(r#"/ Depot";"#, 60), (r#""Depotwert:";"#, 40)])
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 5,
+            account_number_regex: r#""Depot:";"(?P<account>\d+) / Depot";"#.to_string(),
+            balance_amount_regex: r#""Depotwert:";"(?P<amount>[\d,.]+) EUR""#.to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#""Stand:";"(?P<date>[\d.]+)""#.to_string(),
+            period_start_regex: r#""Von:";"(?P<date>[\d.]+)""#.to_string(),
+            period_end_regex: r#""Bis:";"(?P<date>[\d.]+)""#.to_string(),
+            account_type: AccountType::DkbDepot,
+        }
+    }
+
+    fn parse_record(&self, _record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        Err(ParserError::UnsupportedRecord)
+    }
+
+    fn lists_positions(&self) -> bool {
+        true
+    }
+
+    fn parse_position(&self, record: &csv::StringRecord) -> Result<Position, ParserError> {
+        parse_depot_position(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::AccountType;
+    use crate::parsers::parser_factory::ParserFactory;
+    use approx::assert_relative_eq;
+    use chrono::NaiveDate;
+
+    const FILE_PATH: &str = "./src/parsers/testData/dkb_depot_holdings.csv";
+
+    #[test]
+    fn can_parse_dkb_depot_holdings() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser_result.account_name, "501234567");
+        assert_eq!(parser_result.account_type, AccountType::DkbDepot);
+        assert_eq!(
+            parser_result.current_balance_date,
+            NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
+        );
+        assert_relative_eq!(parser_result.current_balance, 4756.0);
+        assert_eq!(parser_result.positions.len(), 1);
+        assert_relative_eq!(parser_result.positions[0].quantity, 50.0);
+        assert_relative_eq!(parser_result.positions[0].gain(), 756.0);
+    }
+}
@@ -0,0 +1,56 @@
+use crate::parsers::*;
+use csv;
+
+pub struct DkbDepotTransactionsParser {}
+
+impl BankStatementParserImplementation for DkbDepotTransactionsParser {
+    fn name(&self) -> &'static str {
+        "DKB Depotumsätze"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[(r#"/ Depot";"#, 60), (r#""Transaktion";"#, 40)])
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 5,
+            account_number_regex: r#""Depot:";"(?P<account>\d+) / Depot";"#.to_string(),
+            balance_amount_regex: r#""Depotwert:";"(?P<amount>[\d,.]+) EUR""#.to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#""Bis:";"(?P<date>[\d.]+)""#.to_string(),
+            period_start_regex: r#""Von:";"(?P<date>[\d.]+)""#.to_string(),
+            period_end_regex: r#""Bis:";"(?P<date>[\d.]+)""#.to_string(),
+            account_type: AccountType::DkbDepot,
+        }
+    }
+
+    fn parse_record(&self, record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        parse_depot_transaction(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::AccountType;
+    use crate::parsers::parser_factory::ParserFactory;
+    use approx::assert_relative_eq;
+
+    const FILE_PATH: &str = "./src/parsers/testData/dkb_depot_transactions.csv";
+
+    #[test]
+    fn can_parse_dkb_depot_transactions() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser_result.account_name, "501234567");
+        assert_eq!(parser_result.account_type, AccountType::DkbDepot);
+        assert_eq!(parser_result.records.len(), 1);
+        assert_relative_eq!(parser_result.records[0].amount, 200.0);
+        assert_eq!(
+            parser_result.records[0].other_side.as_deref(),
+            Some("iShares Core MSCI World UCITS ETF")
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::{self, BufRead, Read};
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use unicode_normalization::UnicodeNormalization;
+
+/// Share of the bytes at odd or even positions which have to be zero for
+/// text without byte order mark to be considered UTF-16.
+const UTF_16_ZERO_SHARE: f64 = 0.4;
+
+/// Detects the encoding from the beginning of a file: a byte order mark
+/// wins, then UTF-16 and UTF-8 are recognized heuristically. Plain ASCII
+/// and text which is no valid UTF-8 use the fallback.
+pub fn detect_encoding(prefix: &[u8], fallback: &'static Encoding) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(prefix) {
+        return encoding;
+    }
+
+    if let Some(encoding) = detect_utf_16(prefix) {
+        return encoding;
+    }
+
+    let valid_utf_8 = match std::str::from_utf8(prefix) {
+        Ok(_) => true,
+        // the prefix may end within a character
+        Err(e) => e.error_len().is_none(),
+    };
+    if valid_utf_8 && !prefix.is_ascii() {
+        UTF_8
+    } else {
+        fallback
+    }
+}
+
+fn detect_utf_16(prefix: &[u8]) -> Option<&'static Encoding> {
+    let pairs = prefix.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+
+    let zeros_at = |offset: usize| {
+        prefix
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|b| **b == 0)
+            .count() as f64
+            / pairs as f64
+    };
+    match (zeros_at(0), zeros_at(1)) {
+        (even, odd) if odd >= UTF_16_ZERO_SHARE && even == 0.0 => Some(UTF_16LE),
+        (even, odd) if even >= UTF_16_ZERO_SHARE && odd == 0.0 => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// Decodes the bytes with the detected encoding into NFC normalized text.
+pub fn decode(bytes: &[u8], fallback: &'static Encoding) -> String {
+    let encoding = detect_encoding(bytes, fallback);
+    encoding.decode_with_bom_removal(bytes).0.nfc().collect()
+}
+
+/// Normalizes the decoded text line by line to NFC, so that an "ü"
+/// written as "u" followed by a combining diaeresis matches the regexes of
+/// the parsers.
+pub struct NormalizingReader<R: BufRead> {
+    inner: R,
+    line: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> NormalizingReader<R> {
+    pub fn new(inner: R) -> NormalizingReader<R> {
+        NormalizingReader {
+            inner,
+            line: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Read for NormalizingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.line.len() {
+            let mut line = String::new();
+            if self.inner.read_line(&mut line)? == 0 {
+                return Ok(0);
+            }
+            self.line = line.nfc().collect::<String>().into_bytes();
+            self.position = 0;
+        }
+
+        let n = buf.len().min(self.line.len() - self.position);
+        buf[..n].copy_from_slice(&self.line[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::WINDOWS_1252;
+
+    use super::*;
+
+    #[test]
+    fn encodings_are_detected() {
+        let text = "Auftraggeber / Begünstigter";
+
+        let windows_1252 = WINDOWS_1252.encode(text).0;
+        let utf_16le: Vec<u8> = text.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+        let mut utf_8_with_bom = vec![0xEF, 0xBB, 0xBF];
+        utf_8_with_bom.extend(text.bytes());
+
+        assert_eq!(detect_encoding(text.as_bytes(), WINDOWS_1252), UTF_8);
+        assert_eq!(detect_encoding(&windows_1252, WINDOWS_1252), WINDOWS_1252);
+        assert_eq!(detect_encoding(&utf_16le, WINDOWS_1252), UTF_16LE);
+        assert_eq!(detect_encoding(&utf_8_with_bom, WINDOWS_1252), UTF_8);
+        assert_eq!(detect_encoding(b"Buchung;Betrag", UTF_8), UTF_8);
+        // cut within the two bytes of the "ü"
+        assert_eq!(detect_encoding(&text.as_bytes()[..19], WINDOWS_1252), UTF_8);
+
+        assert_eq!(decode(&utf_8_with_bom, WINDOWS_1252), text);
+        assert_eq!(decode(&windows_1252, WINDOWS_1252), text);
+    }
+
+    #[test]
+    fn text_is_normalized() {
+        let decomposed = "Begu\u{0308}nstigter;\nzweite Zeile\n";
+
+        let mut normalized = String::new();
+        NormalizingReader::new(decomposed.as_bytes())
+            .read_to_string(&mut normalized)
+            .unwrap();
+
+        assert_eq!(normalized, "Begünstigter;\nzweite Zeile\n");
+        assert_eq!(decode(decomposed.as_bytes(), WINDOWS_1252), normalized);
+    }
+}
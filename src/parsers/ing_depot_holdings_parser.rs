@@ -0,0 +1,74 @@
+use super::BankStatementParserImplementation;
+use super::ParserError;
+use crate::parsers::*;
+
+pub struct IngDepotHoldingsParser {}
+
+impl BankStatementParserImplementation for IngDepotHoldingsParser {
+    fn name(&self) -> &'static str {
+        "ING Depotübersicht"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[("Depotübersicht;", 80), ("Bank;ING", 20)])
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 7,
+            account_number_regex: r#"Depot;(?P<account>\d+)"#.to_string(),
+            balance_amount_regex: r#"Depotwert;(?P<amount>[\d,.]+)"#.to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#"Stand;(?P<date>[\d.]+)"#.to_string(),
+            period_start_regex: r#"Zeitraum;(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#"Zeitraum;[\d.]+ - (?P<date>[\d.]+)"#.to_string(),
+            account_type: AccountType::IngDepot,
+        }
+    }
+
+    fn parse_record(&self, _record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        Err(ParserError::UnsupportedRecord)
+    }
+
+    fn lists_positions(&self) -> bool {
+        true
+    }
+
+    fn parse_position(&self, record: &csv::StringRecord) -> Result<Position, ParserError> {
+        parse_depot_position(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::AccountType;
+    use crate::parsers::parser_factory::ParserFactory;
+    use approx::assert_relative_eq;
+    use chrono::NaiveDate;
+
+    const FILE_PATH: &str = "./src/parsers/testData/ing_depot_holdings.csv";
+
+    #[test]
+    fn can_parse_ing_depot_holdings() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser_result.account_name, "1234567890");
+        assert_eq!(parser_result.account_type, AccountType::IngDepot);
+        assert_eq!(
+            parser_result.current_balance_date,
+            NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
+        );
+        assert_relative_eq!(parser_result.current_balance, 15103.96);
+        assert!(parser_result.records.is_empty());
+
+        let position = &parser_result.positions[0];
+        assert_eq!(position.isin, "IE00B4L5Y983");
+        assert_eq!(position.name, "iShares Core MSCI World UCITS ETF");
+        assert_relative_eq!(position.quantity, 120.5);
+        assert_relative_eq!(position.cost_basis, 9800.0);
+        assert_relative_eq!(position.market_value, 11461.96);
+        assert_eq!(parser_result.positions.len(), 2);
+    }
+}
@@ -0,0 +1,66 @@
+use super::BankStatementParserImplementation;
+use super::ParserError;
+use crate::parsers::*;
+
+pub struct IngDepotTransactionsParser {}
+
+impl BankStatementParserImplementation for IngDepotTransactionsParser {
+    fn name(&self) -> &'static str {
+        "ING Depotumsätze"
+    }
+
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[("Depotumsätze;", 80), ("Bank;ING", 20)])
+    }
+
+    fn get_header_parser(&self) -> BankStatementHeaderParser {
+        BankStatementHeaderParser {
+            header_length: 7,
+            account_number_regex: r#"Depot;(?P<account>\d+)"#.to_string(),
+            balance_amount_regex: r#"Depotwert;(?P<amount>[\d,.]+)"#.to_string(),
+            parse_amount: parse_float,
+            balance_date_regex: r#"Datei erstellt am: (?P<date>[\d.]+)"#.to_string(),
+            period_start_regex: r#"Zeitraum;(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#"Zeitraum;[\d.]+ - (?P<date>[\d.]+)"#.to_string(),
+            account_type: AccountType::IngDepot,
+        }
+    }
+
+    fn parse_record(&self, record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+        parse_depot_transaction(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{account_history::StatementPeriod, AccountType};
+    use crate::parsers::parser_factory::ParserFactory;
+    use approx::assert_relative_eq;
+    use chrono::NaiveDate;
+
+    const FILE_PATH: &str = "./src/parsers/testData/ing_depot_transactions.csv";
+
+    #[test]
+    fn can_parse_ing_depot_transactions() {
+        let parser = ParserFactory::create(FILE_PATH).unwrap();
+
+        let parser_result = parser.parse(FILE_PATH).unwrap();
+
+        assert_eq!(parser_result.account_name, "1234567890");
+        assert_eq!(parser_result.account_type, AccountType::IngDepot);
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
+        assert!(parser_result.positions.is_empty());
+
+        let purchase = &parser_result.records[0];
+        assert_relative_eq!(purchase.amount, 500.0);
+        assert_eq!(purchase.booking_text, "Kauf");
+        assert_eq!(purchase.purpose.as_deref(), Some("IE00B4L5Y983 5,25 Stück"));
+        assert_relative_eq!(parser_result.records[1].amount, -360.0);
+    }
+}
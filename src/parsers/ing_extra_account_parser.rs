@@ -4,19 +4,15 @@ use crate::parsers::*;
 
 pub struct IngExtraAccountParser {}
 
-impl IngExtraAccountParser {
-    pub fn can_parse(file_path: &str) -> Result<bool, ParserError>
-    {
-        let mut decoder = get_decoded_file_reader(file_path);
-
-        let mut buf = String::new();
-        decoder.read_to_string(&mut buf).map_err(|_| ParserError::FileReadError)?;
+impl BankStatementParserImplementation for IngExtraAccountParser {
+    fn name(&self) -> &'static str {
+        "ING Extra-Konto"
+    }
 
-        Ok(buf.contains(r#"Kontoname;Extra-Konto"#))
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[("Kontoname;Extra-Konto", 80), ("Bank;ING", 20)])
     }
-}
 
-impl BankStatementParserImplementation for IngExtraAccountParser {
     fn get_header_parser(&self) -> BankStatementHeaderParser {
         BankStatementHeaderParser {
             header_length: 12,
@@ -24,6 +20,8 @@ impl BankStatementParserImplementation for IngExtraAccountParser {
             balance_amount_regex: r#"Saldo;(?P<amount>[+-]?[\d,.]+);EUR"#.to_string(),
             parse_amount: parse_float,
             balance_date_regex: r#"Datei erstellt am: (?P<date>[\d.]+)"#.to_string(),
+            period_start_regex: r#"Zeitraum;(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#"Zeitraum;[\d.]+ - (?P<date>[\d.]+)"#.to_string(),
             account_type: AccountType::IngExtraAccount,
         }
     }
@@ -35,13 +33,23 @@ impl BankStatementParserImplementation for IngExtraAccountParser {
             other_side: Some(record[2].to_string()),
             booking_text: record[3].to_string(),
             purpose: Some(record[4].to_string()),
+            balance: parse_float(&record[5]).ok(),
+            canonical_counterparty: None,
+            original_currency: None,
+            // the exports leave out pre-authorized bookings
+            status: BookingStatus::Booked,
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{AccountRecord, AccountType};
+    use crate::model::{account_history::StatementPeriod, AccountRecord, AccountType, BookingStatus};
     use crate::parsers::parser_factory::ParserFactory;
     use approx::assert_relative_eq;
     use chrono::NaiveDate;
@@ -62,14 +70,32 @@ mod tests {
                 date: chrono::NaiveDate::from_ymd_opt(2023, 12, 29).unwrap(),
                 other_side: Some("".to_string()),
                 booking_text: "Abschluss".to_string(),
-                purpose: Some("".to_string())
+                purpose: Some("".to_string()),
+                balance: Some(12345.01),
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE08 5001 0517 5553 6114 73".to_string())
             },
             AccountRecord {
                 amount: -3.22,
                 date: chrono::NaiveDate::from_ymd_opt(2023, 12, 29).unwrap(),
                 other_side: Some("".to_string()),
                 booking_text: "Zuschlag".to_string(),
-                purpose: Some("".to_string())
+                purpose: Some("".to_string()),
+                balance: Some(29953.47),
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE08 5001 0517 5553 6114 73".to_string())
             }
         ];
 
@@ -80,6 +106,13 @@ mod tests {
             parser_result.current_balance_date,
             NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
         );
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2023, 8, 11).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
         assert_eq!(parser_result.records, expected_records);
     }
 }
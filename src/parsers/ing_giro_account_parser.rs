@@ -4,19 +4,15 @@ use crate::parsers::*;
 
 pub struct IngGiroAccountParser {}
 
-impl IngGiroAccountParser {
-    pub fn can_parse(file_path: &str) -> Result<bool, ParserError>
-    {
-        let mut decoder = get_decoded_file_reader(file_path);
-
-        let mut buf = String::new();
-        decoder.read_to_string(&mut buf).map_err(|_| ParserError::FileReadError)?;
+impl BankStatementParserImplementation for IngGiroAccountParser {
+    fn name(&self) -> &'static str {
+        "ING Girokonto"
+    }
 
-        Ok(buf.contains(r#"Kontoname;Girokonto"#))
+    fn confidence(&self, prefix: &str) -> u32 {
+        marker_confidence(prefix, &[("Kontoname;Girokonto", 80), ("Bank;ING", 20)])
     }
-}
 
-impl BankStatementParserImplementation for IngGiroAccountParser {
     fn get_header_parser(&self) -> BankStatementHeaderParser {
         BankStatementHeaderParser {
             header_length: 12,
@@ -24,6 +20,8 @@ impl BankStatementParserImplementation for IngGiroAccountParser {
             balance_amount_regex: r#"Saldo;(?P<amount>[+-]?[\d,.]+);EUR"#.to_string(),
             parse_amount: parse_float,
             balance_date_regex: r#"Datei erstellt am: (?P<date>[\d.]+)"#.to_string(),
+            period_start_regex: r#"Zeitraum;(?P<date>[\d.]+) - "#.to_string(),
+            period_end_regex: r#"Zeitraum;[\d.]+ - (?P<date>[\d.]+)"#.to_string(),
             account_type: AccountType::IngGiroAccount,
         }
     }
@@ -35,13 +33,23 @@ impl BankStatementParserImplementation for IngGiroAccountParser {
             other_side: Some(record[2].to_string()),
             booking_text: record[3].to_string(),
             purpose: Some(record[4].to_string()),
+            balance: None,
+            canonical_counterparty: None,
+            original_currency: None,
+            // the exports leave out pre-authorized bookings
+            status: BookingStatus::Booked,
+            category: None,
+            note: None,
+            tags: vec![],
+            id: None,
+            account_name: None,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::{AccountRecord, AccountType};
+    use crate::model::{account_history::StatementPeriod, AccountRecord, AccountType, BookingStatus};
     use crate::parsers::parser_factory::ParserFactory;
     use approx::assert_relative_eq;
     use chrono::NaiveDate;
@@ -61,14 +69,32 @@ mod tests {
                 date: chrono::NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
                 other_side: Some("VISA AMZN MKTP DE*CB3UF2VD5".to_string()),
                 booking_text: "Lastschrift".to_string(),
-                purpose: Some("NR XXXX 5015 800-279-662 LU KAUFUMSATZ 02.09 16.98 101352 ARN74279814246101247805768".to_string())
+                purpose: Some("NR XXXX 5015 800-279-662 LU KAUFUMSATZ 02.09 16.98 101352 ARN74279814246101247805768".to_string()),
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE25 5001 0123 4567 8910 11".to_string())
             },
             AccountRecord {
                 amount: 5000.72,
                 date: chrono::NaiveDate::from_ymd_opt(2024, 8, 13).unwrap(),
                 other_side: Some("Company".to_string()),
                 booking_text: "Gehalt/Rente".to_string(),
-                purpose: Some("LOHN / GEHALT 08/24".to_string())
+                purpose: Some("LOHN / GEHALT 08/24".to_string()),
+                balance: None,
+                canonical_counterparty: None,
+                original_currency: None,
+                status: BookingStatus::Booked,
+                category: None,
+                note: None,
+                tags: vec![],
+                id: None,
+                account_name: Some("DE25 5001 0123 4567 8910 11".to_string())
             }
         ];
 
@@ -79,6 +105,13 @@ mod tests {
             parser_result.current_balance_date,
             NaiveDate::from_ymd_opt(2024, 9, 4).unwrap()
         );
+        assert_eq!(
+            parser_result.period,
+            Some(StatementPeriod {
+                from: NaiveDate::from_ymd_opt(2023, 8, 11).unwrap(),
+                to: NaiveDate::from_ymd_opt(2024, 9, 4).unwrap(),
+            })
+        );
         assert_eq!(parser_result.records, expected_records);
     }
 }
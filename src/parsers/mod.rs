@@ -1,9 +1,9 @@
 use bank_statement_header_parser::BankStatementHeaderParser;
-use encoding_rs_io::DecodeReaderBytes;
 use thiserror::Error;
-use crate::model::{account_history::AccountHistory, *};
-use std::{self, io::BufReader};
-use encoding_rs::WINDOWS_1252;
+use crate::model::{account_history::AccountHistory, portfolio::Position, *};
+use std::{self, io::{BufRead, BufReader, Cursor}};
+use encoding_rs::{Encoding, WINDOWS_1252};
+use encoding_detection::{detect_encoding, NormalizingReader};
 use encoding_rs_io::DecodeReaderBytesBuilder;
 use regex;
 use std::io::Read;
@@ -11,11 +11,19 @@ use std::fs::File;
 
 mod bank_statement_header_parser;
 
+pub mod encoding_detection;
 pub mod dkb_account_parser;
+pub mod dkb_account_v2_parser;
 pub mod dkb_credit_card_parser;
+pub mod dkb_credit_card_v2_parser;
+pub mod dkb_depot_holdings_parser;
+pub mod dkb_depot_transactions_parser;
 pub mod ing_giro_account_parser;
 pub mod ing_extra_account_parser;
+pub mod ing_depot_holdings_parser;
+pub mod ing_depot_transactions_parser;
 pub mod parser_factory;
+pub mod parser_registry;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
@@ -24,11 +32,30 @@ pub enum ParserError {
     #[error("Invalid float.")]
     FloatError,
     #[error("Could not read file")]
-    FileReadError
+    FileReadError,
+    #[error("Record not supported by this format.")]
+    UnsupportedRecord,
+    #[error(transparent)]
+    UnknownFormat(#[from] parser_registry::DetectionError)
 }
 
 pub trait BankStatementParserImplementation {
+    fn name(&self) -> &'static str;
+    /// Rates how likely a file starting with the prefix is a statement of
+    /// this format, 0 if it is none.
+    fn confidence(&self, prefix: &str) -> u32;
+    /// Encoding of files without byte order mark which are no valid UTF-8.
+    fn fallback_encoding(&self) -> &'static Encoding {
+        WINDOWS_1252
+    }
     fn parse_record(&self, record: &csv::StringRecord) -> Result<AccountRecord, ParserError>;
+    /// Holdings exports list the positions of a depot instead of bookings.
+    fn lists_positions(&self) -> bool {
+        false
+    }
+    fn parse_position(&self, _record: &csv::StringRecord) -> Result<Position, ParserError> {
+        Err(ParserError::UnsupportedRecord)
+    }
     fn get_header_parser(&self) -> BankStatementHeaderParser;
 }
 
@@ -38,18 +65,35 @@ pub struct BankStatementParser {
 
 impl BankStatementParser {
     pub fn parse(&self, file_path: &str) -> Result<AccountHistory, ParserError> {
-        let mut buf_reader = get_decoded_lines_reader(file_path);
+        let file = File::open(file_path).map_err(|_| ParserError::FileReadError)?;
+        self.parse_reader(file)
+    }
+
+    /// Parses a statement from any source, e.g. stdin or a buffer.
+    pub fn parse_reader(&self, reader: impl Read) -> Result<AccountHistory, ParserError> {
+        let mut buf_reader = get_decoded_lines_reader(reader, self.implementation.fallback_encoding())?;
         let account_history = self.parse_file_header(&mut buf_reader)?;
+        if self.implementation.lists_positions() {
+            // the balance of a depot is the market value of its positions
+            let positions = self.parse_positions(&mut buf_reader)?;
+            return Ok(AccountHistory {
+                current_balance: positions.iter().map(|p| p.market_value).sum(),
+                positions,
+                ..account_history
+            });
+        }
         let records = self.parse_records(&mut buf_reader)?;
-        Ok(AccountHistory {
+        let mut account_history = AccountHistory {
             records,
             ..account_history
-        })
+        };
+        account_history.name_records();
+        Ok(account_history)
     }
 
     fn parse_file_header(
         &self,
-        line_reader: &mut BufReader<DecodeReaderBytes<impl Read, Vec<u8>>>,
+        line_reader: &mut impl BufRead,
     ) -> Result<AccountHistory, ParserError> {
         let header_parser = self.implementation.get_header_parser();
         header_parser.parse(line_reader)
@@ -57,7 +101,7 @@ impl BankStatementParser {
 
     fn parse_records(
         &self,
-        line_reader: &mut BufReader<DecodeReaderBytes<impl Read, Vec<u8>>>,
+        line_reader: &mut impl BufRead,
     ) -> Result<Vec<AccountRecord>, ParserError> {
         let mut csv_reader = csv::ReaderBuilder::new()
             .delimiter(b';')
@@ -65,25 +109,120 @@ impl BankStatementParser {
     
         let account_records: Vec<AccountRecord> = csv_reader
             .records()
-            .into_iter()
             .filter(|r| r.is_ok())
             .map(|r| self.implementation.parse_record(&r.unwrap()).unwrap())
             .collect();
     
         Ok(account_records)
     }
+
+    fn parse_positions(
+        &self,
+        line_reader: &mut impl BufRead,
+    ) -> Result<Vec<Position>, ParserError> {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .from_reader(line_reader);
+
+        csv_reader
+            .records()
+            .filter_map(|r| r.ok())
+            .map(|r| self.implementation.parse_position(&r))
+            .collect()
+    }
+}
+
+/// Reads the first `DETECTION_PREFIX_BYTES` bytes, which are used to
+/// detect the format and the encoding.
+pub fn read_prefix(reader: &mut impl Read) -> Result<Vec<u8>, ParserError> {
+    let mut prefix = Vec::new();
+    reader
+        .take(parser_registry::DETECTION_PREFIX_BYTES)
+        .read_to_end(&mut prefix)
+        .map_err(|_| ParserError::FileReadError)?;
+    Ok(prefix)
+}
+
+/// Decodes the text with the encoding detected from its beginning.
+fn get_decoded_reader(mut reader: impl Read, fallback: &'static Encoding) -> Result<impl Read, ParserError> {
+    let prefix = read_prefix(&mut reader)?;
+    let encoding = detect_encoding(&prefix, fallback);
+
+    Ok(DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .build(Cursor::new(prefix).chain(reader)))
+}
+
+fn get_decoded_lines_reader(reader: impl Read, fallback: &'static Encoding) -> Result<BufReader<impl Read>, ParserError> {
+    let decoder = get_decoded_reader(reader, fallback)?;
+    Ok(BufReader::new(NormalizingReader::new(BufReader::new(decoder))))
+}
+
+/// Sums the scores of the markers found in the prefix.
+fn marker_confidence(prefix: &str, markers: &[(&str, u32)]) -> u32 {
+    markers
+        .iter()
+        .filter(|(marker, _)| prefix.contains(marker))
+        .map(|(_, score)| score)
+        .sum()
+}
+
+/// Parses a depot transaction with the columns date, transaction type,
+/// ISIN, name, quantity, price and amount. The amount of the record is the
+/// money invested, the opposite of the cash booking.
+fn parse_depot_transaction(record: &csv::StringRecord) -> Result<AccountRecord, ParserError> {
+    Ok(AccountRecord {
+        amount: -parse_float(&record[6])?,
+        date: parse_date(&record[0])?,
+        other_side: Some(record[3].to_string()),
+        booking_text: record[1].to_string(),
+        purpose: Some(format!("{} {} Stück", &record[2], &record[4])),
+        balance: None,
+        canonical_counterparty: None,
+        original_currency: None,
+        status: BookingStatus::Booked,
+        category: None,
+        note: None,
+        tags: vec![],
+        id: None,
+        account_name: None,
+    })
+}
+
+/// Parses a position with the columns ISIN, name, quantity, cost basis,
+/// price and market value.
+fn parse_depot_position(record: &csv::StringRecord) -> Result<Position, ParserError> {
+    Ok(Position {
+        isin: record[0].to_string(),
+        name: record[1].to_string(),
+        quantity: parse_float(&record[2])?,
+        cost_basis: parse_float(&record[3])?,
+        market_value: parse_float(&record[5])?,
+    })
+}
+
+/// Returns the currency of an original amount like "12,00 USD".
+fn parse_original_currency(original_amount: &str) -> Option<String> {
+    let currency = original_amount.split_whitespace().last()?;
+    if currency.chars().all(|c| c.is_ascii_alphabetic()) && currency != "EUR" {
+        Some(currency.to_string())
+    } else {
+        None
+    }
 }
 
-fn get_decoded_file_reader(file_path: &str) -> DecodeReaderBytes<impl Read, Vec<u8>> {
-    let file = File::open(file_path).unwrap();
-    DecodeReaderBytesBuilder::new()
-        .encoding(Some(WINDOWS_1252))
-        .build(file)
+/// Newer DKB exports mark bookings which are not yet booked as
+/// "Vorgemerkt" instead of "Gebucht".
+fn parse_dkb_status(status: &str) -> BookingStatus {
+    match status {
+        "Vorgemerkt" => BookingStatus::Pending,
+        _ => BookingStatus::Booked,
+    }
 }
 
-fn get_decoded_lines_reader(file_path: &str) -> BufReader<DecodeReaderBytes<impl Read, Vec<u8>>> {
-    let decoder = get_decoded_file_reader(file_path);
-    BufReader::new(decoder)
+/// Parses amounts like "-1.234,56 €".
+fn parse_euro_amount(s: &str) -> Result<f64, ParserError> {
+    parse_float(s.trim_end_matches('€').trim())
 }
 
 fn parse_float(s: &str) -> Result<f64, ParserError> {
@@ -94,6 +233,11 @@ fn parse_std_float(s: &str) -> Result<f64, ParserError> {
     s.parse::<f64>().map_err(|_|ParserError::FloatError)
 }
 
+/// Parses dates with a two digit year like "04.09.24".
+fn parse_short_date(s: &str) -> Result<chrono::NaiveDate, ParserError> {
+    chrono::NaiveDate::parse_from_str(s, "%d.%m.%y").map_err(|_| ParserError::InvalidDate)
+}
+
 fn parse_date(s: &str) -> Result<chrono::NaiveDate, ParserError> {
     let result = chrono::NaiveDate::parse_from_str(s, "%d.%m.%Y");
 
@@ -1,41 +1,67 @@
-use thiserror::Error;
+use std::io::{Cursor, Read};
 
-use super::{dkb_account_parser::DkbAccountParser, dkb_credit_card_parser::DkbCreditCardParser, ing_extra_account_parser::IngExtraAccountParser, ing_giro_account_parser::IngGiroAccountParser, BankStatementParser, BankStatementParserImplementation};
+use crate::model::account_history::AccountHistory;
 
-#[derive(Error, Debug)]
-pub enum ParserFactoryError {
-    #[error("No parser found.")]
-    NoParserFound
-}
+use super::{parser_registry::{DetectionError, ParserRegistry}, read_prefix, BankStatementParser, ParserError};
+
+pub type ParserFactoryError = DetectionError;
 
 pub struct ParserFactory { }
 
 impl ParserFactory {
+    /// Creates the parser of the best matching format of the default
+    /// registry.
     pub fn create(file_path: &str) -> Result<BankStatementParser, ParserFactoryError> {
-        let implementation = ParserFactory::get_implementation(file_path)?;
-        
+        ParserFactory::create_from(ParserRegistry::default(), file_path)
+    }
+
+    pub fn create_from(registry: ParserRegistry, file_path: &str) -> Result<BankStatementParser, ParserFactoryError> {
+        let implementation = registry.take_parser(file_path)?;
+
+        Ok(BankStatementParser {
+            implementation
+        })
+    }
+
+    /// Creates the parser for a file starting with the prefix.
+    pub fn create_for_prefix(prefix: &[u8]) -> Result<BankStatementParser, ParserFactoryError> {
+        let implementation = ParserRegistry::default().take_parser_for(prefix)?;
+
         Ok(BankStatementParser {
             implementation
         })
     }
 
-    fn get_implementation(file_path: &str) -> Result<Box<dyn BankStatementParserImplementation>, ParserFactoryError> {
-        if DkbAccountParser::can_parse(file_path).unwrap() {
-            return Ok(Box::new(DkbAccountParser {}))
-        }
+    /// Detects the format and parses the statement without touching the
+    /// filesystem.
+    pub fn parse_reader(mut reader: impl Read) -> Result<AccountHistory, ParserError> {
+        let prefix = read_prefix(&mut reader)?;
+        let parser = ParserFactory::create_for_prefix(&prefix)?;
+        parser.parse_reader(Cursor::new(prefix).chain(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if DkbCreditCardParser::can_parse(file_path).unwrap() {
-            return Ok(Box::new(DkbCreditCardParser {}))
-        }
+    const FILE_PATH: &str = "./src/parsers/testData/ing_giro_account_statement.csv";
 
-        if IngGiroAccountParser::can_parse(file_path).unwrap() {
-            return Ok(Box::new(IngGiroAccountParser {}))
-        }
+    #[test]
+    fn statements_can_be_parsed_from_memory() {
+        let bytes = include_bytes!("testData/ing_giro_account_statement.csv");
 
-        if IngExtraAccountParser::can_parse(file_path).unwrap() {
-            return Ok(Box::new(IngExtraAccountParser {}))
-        }
+        let from_memory = ParserFactory::parse_reader(&bytes[..]).unwrap();
+        let from_file = ParserFactory::create(FILE_PATH).unwrap().parse(FILE_PATH).unwrap();
 
-        Err(ParserFactoryError::NoParserFound)
+        assert_eq!(from_memory, from_file);
+        assert_eq!(from_memory.records.len(), 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unknown_formats_are_reported() {
+        let result = ParserFactory::parse_reader("Datum;Betrag\n01.01.2024;1,00\n".as_bytes());
+
+        assert!(matches!(result, Err(ParserError::UnknownFormat(DetectionError::NoParserFound))));
+    }
+}
@@ -0,0 +1,167 @@
+use std::fs::File;
+
+use thiserror::Error;
+
+use super::{
+    dkb_account_parser::DkbAccountParser, dkb_account_v2_parser::DkbAccountV2Parser,
+    dkb_credit_card_parser::DkbCreditCardParser, dkb_credit_card_v2_parser::DkbCreditCardV2Parser,
+    dkb_depot_holdings_parser::DkbDepotHoldingsParser,
+    dkb_depot_transactions_parser::DkbDepotTransactionsParser, encoding_detection::decode,
+    ing_depot_holdings_parser::IngDepotHoldingsParser,
+    ing_depot_transactions_parser::IngDepotTransactionsParser,
+    ing_extra_account_parser::IngExtraAccountParser, ing_giro_account_parser::IngGiroAccountParser,
+    read_prefix, BankStatementParserImplementation,
+};
+
+/// Number of bytes at the beginning of a file used to detect its format.
+pub const DETECTION_PREFIX_BYTES: u64 = 4096;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DetectionError {
+    #[error("Could not read file.")]
+    FileReadError,
+    #[error("No parser found.")]
+    NoParserFound,
+    #[error("Ambiguous format, could be {}.", .0.join(" or "))]
+    AmbiguousFormat(Vec<&'static str>),
+}
+
+/// The parsers known for detecting the format of a statement.
+pub struct ParserRegistry {
+    parsers: Vec<Box<dyn BankStatementParserImplementation>>,
+}
+
+impl Default for ParserRegistry {
+    fn default() -> Self {
+        let mut registry = ParserRegistry { parsers: vec![] };
+        registry.register(Box::new(DkbAccountParser {}));
+        registry.register(Box::new(DkbCreditCardParser {}));
+        registry.register(Box::new(DkbAccountV2Parser {}));
+        registry.register(Box::new(DkbCreditCardV2Parser {}));
+        registry.register(Box::new(IngGiroAccountParser {}));
+        registry.register(Box::new(IngExtraAccountParser {}));
+        registry.register(Box::new(IngDepotHoldingsParser {}));
+        registry.register(Box::new(IngDepotTransactionsParser {}));
+        registry.register(Box::new(DkbDepotHoldingsParser {}));
+        registry.register(Box::new(DkbDepotTransactionsParser {}));
+        registry
+    }
+}
+
+/// Decodes the beginning of a file, binary files result in `None`.
+fn decode_prefix(prefix: &[u8]) -> Option<String> {
+    // the parsers read their files with their own fallback encoding, the
+    // markers used for detection are plain ASCII
+    let prefix = decode(prefix, encoding_rs::WINDOWS_1252);
+    if prefix.contains('\0') {
+        None
+    } else {
+        Some(prefix)
+    }
+}
+
+impl ParserRegistry {
+    pub fn register(&mut self, parser: Box<dyn BankStatementParserImplementation>) {
+        self.parsers.push(parser);
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.parsers.iter().map(|p| p.name()).collect()
+    }
+
+    /// Picks the parser with the highest confidence for the prefix. Several
+    /// parsers with the same confidence are reported as ambiguous.
+    pub fn detect(&self, prefix: &str) -> Result<usize, DetectionError> {
+        let confidences: Vec<u32> = self.parsers.iter().map(|p| p.confidence(prefix)).collect();
+        let best = confidences.iter().copied().max().unwrap_or(0);
+        if best == 0 {
+            return Err(DetectionError::NoParserFound);
+        }
+
+        let best_parsers: Vec<usize> = (0..self.parsers.len())
+            .filter(|i| confidences[*i] == best)
+            .collect();
+        match best_parsers[..] {
+            [i] => Ok(i),
+            _ => Err(DetectionError::AmbiguousFormat(
+                best_parsers
+                    .iter()
+                    .map(|i| self.parsers[*i].name())
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Detects the format from the beginning of a file, see
+    /// `read_prefix`, and takes the matching parser out of the registry.
+    pub fn take_parser_for(
+        mut self,
+        prefix: &[u8],
+    ) -> Result<Box<dyn BankStatementParserImplementation>, DetectionError> {
+        let prefix = decode_prefix(prefix).ok_or(DetectionError::NoParserFound)?;
+        let i = self.detect(&prefix)?;
+        Ok(self.parsers.swap_remove(i))
+    }
+
+    pub fn take_parser(
+        self,
+        file_path: &str,
+    ) -> Result<Box<dyn BankStatementParserImplementation>, DetectionError> {
+        let mut file = File::open(file_path).map_err(|_| DetectionError::FileReadError)?;
+        let prefix = read_prefix(&mut file).map_err(|_| DetectionError::FileReadError)?;
+        self.take_parser_for(&prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_most_confident_parser_is_chosen() {
+        let registry = ParserRegistry::default();
+
+        let prefix = "Umsatzanzeige;Datei erstellt am: 04.09.2024 13:54\n\nIBAN;DE25\nKontoname;Extra-Konto\nBank;ING\n";
+
+        assert_eq!(
+            registry.names()[registry.detect(prefix).unwrap()],
+            "ING Extra-Konto"
+        );
+        assert_eq!(
+            registry.detect("%PDF-1.7"),
+            Err(DetectionError::NoParserFound)
+        );
+    }
+
+    #[test]
+    fn equally_confident_parsers_are_ambiguous() {
+        let registry = ParserRegistry::default();
+
+        let prefix = "Kontoname;Girokonto\nKontoname;Extra-Konto\n";
+
+        assert_eq!(
+            registry.detect(prefix),
+            Err(DetectionError::AmbiguousFormat(vec![
+                "ING Girokonto",
+                "ING Extra-Konto"
+            ]))
+        );
+    }
+
+    #[test]
+    fn binary_and_missing_files_are_not_parsed() {
+        let path = std::env::temp_dir().join(format!("accounts_binary_{}.png", std::process::id()));
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let result = ParserRegistry::default().take_parser(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.err(), Some(DetectionError::NoParserFound));
+        assert_eq!(
+            ParserRegistry::default()
+                .take_parser("./does/not/exist.csv")
+                .err(),
+            Some(DetectionError::FileReadError)
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::model::{
+    account_history::{AccountHistory, StatementPeriod},
+    portfolio::Position,
+    AccountRecord, AccountType, BookingStatus,
+};
+
+pub const STORE_FILE_NAME: &str = "accounts_store.json";
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("Could not read store file.")]
+    FileReadError,
+    #[error("Could not write store file.")]
+    FileWriteError,
+    #[error("Error parsing json.")]
+    JsonParsingError,
+}
+
+/// Describes which statement file added records to the store.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ImportRecord {
+    pub id: usize,
+    pub source: String,
+    pub account_name: String,
+    pub imported_at: NaiveDateTime,
+    pub new_records: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StoredRecord {
+    pub import_id: usize,
+    #[serde(flatten)]
+    pub record: AccountRecord,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct StoredAccount {
+    pub account_name: String,
+    pub account_type: AccountType,
+    /// Unknown for a depot until a holdings export has been imported.
+    #[serde(flatten)]
+    pub balance: Option<StoredBalance>,
+    /// Covers the periods of all imported statements.
+    #[serde(default)]
+    pub period: Option<StatementPeriod>,
+    /// Sorted newest first, like the records of an `AccountHistory`.
+    pub records: Vec<StoredRecord>,
+    /// The positions of the most recent holdings export of a depot.
+    #[serde(default)]
+    pub positions: Vec<Position>,
+}
+
+impl StoredAccount {
+    /// The date of the balance, or the end of the known bookings if the
+    /// balance is unknown.
+    fn balance_date(&self) -> NaiveDate {
+        match self.balance {
+            Some(balance) => balance.current_balance_date,
+            None => self
+                .period
+                .map(|p| p.to)
+                .or(self.records.first().map(|r| r.record.date))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct StoredBalance {
+    pub current_balance_date: NaiveDate,
+    pub current_balance: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct TransactionStore {
+    pub imports: Vec<ImportRecord>,
+    pub accounts: Vec<StoredAccount>,
+}
+
+/// Identifies equal records. The amount is compared in cents so that it
+/// can be part of a hashable key.
+type RecordKey = (NaiveDate, i64, Option<String>, String, Option<String>);
+
+fn record_key(record: &AccountRecord) -> RecordKey {
+    (
+        record.date,
+        (record.amount * 100.0).round() as i64,
+        record.other_side.clone(),
+        record.booking_text.clone(),
+        record.purpose.clone(),
+    )
+}
+
+impl TransactionStore {
+    /// Loads the store from a file. A missing file yields an empty store.
+    pub fn load(path: &Path) -> Result<TransactionStore, StoreError> {
+        let file_contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(TransactionStore::default()),
+            Err(_) => return Err(StoreError::FileReadError),
+        };
+
+        serde_json::from_str(&file_contents).map_err(|_| StoreError::JsonParsingError)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), StoreError> {
+        let contents =
+            serde_json::to_string_pretty(self).map_err(|_| StoreError::FileWriteError)?;
+
+        // write to a temporary file first so that an interrupted write
+        // cannot corrupt the existing store
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, contents).map_err(|_| StoreError::FileWriteError)?;
+        fs::rename(&tmp_path, path).map_err(|_| StoreError::FileWriteError)
+    }
+
+    /// Adds all records of the history which are not yet stored for its
+    /// account and returns the number of added records.
+    ///
+    /// Records are compared as multisets: if a statement contains the same
+    /// transaction twice on one day, both are kept, but importing an
+    /// overlapping statement again does not add them a second time.
+    ///
+    /// Pending records are left out, since they may still change or
+    /// disappear. Their booked versions are added by a later import.
+    pub fn import(
+        &mut self,
+        history: AccountHistory,
+        source: &str,
+        imported_at: NaiveDateTime,
+    ) -> usize {
+        let import_id = self.imports.iter().map(|i| i.id).max().unwrap_or(0) + 1;
+        // the balance of a depot is only known from its holdings exports
+        let balance: Option<(NaiveDate, f64)> =
+            if history.account_type.is_depot() && history.positions.is_empty() {
+                None
+            } else {
+                Some((history.current_balance_date, history.current_balance))
+            };
+
+        let account = match self
+            .accounts
+            .iter_mut()
+            .position(|a| a.account_name == history.account_name)
+        {
+            Some(i) => &mut self.accounts[i],
+            None => {
+                self.accounts.push(StoredAccount {
+                    account_name: history.account_name.clone(),
+                    account_type: history.account_type,
+                    balance: None,
+                    period: None,
+                    records: vec![],
+                    positions: vec![],
+                });
+                self.accounts.last_mut().unwrap()
+            }
+        };
+
+        let mut stored_counts: HashMap<RecordKey, usize> = HashMap::new();
+        for stored in account.records.iter() {
+            *stored_counts.entry(record_key(&stored.record)).or_default() += 1;
+        }
+
+        let mut new_records = Vec::new();
+        for record in history.records {
+            if record.status == BookingStatus::Pending {
+                continue;
+            }
+            match stored_counts.get_mut(&record_key(&record)) {
+                Some(count) if *count > 0 => *count -= 1,
+                _ => new_records.push(StoredRecord { import_id, record }),
+            }
+        }
+
+        account.period = match (account.period, history.period) {
+            (Some(a), Some(b)) => Some(a.union(&b)),
+            (a, b) => a.or(b),
+        };
+
+        if let Some((current_balance_date, current_balance)) = balance {
+            let is_newer = account
+                .balance
+                .is_none_or(|b| current_balance_date > b.current_balance_date);
+            if is_newer {
+                account.balance = Some(StoredBalance {
+                    current_balance_date,
+                    current_balance,
+                });
+                account.positions = history.positions;
+            }
+        }
+
+        let new_record_count = new_records.len();
+        if new_record_count == 0 {
+            return 0;
+        }
+
+        account.records.extend(new_records);
+        account
+            .records
+            .sort_by_key(|r| std::cmp::Reverse(r.record.date));
+
+        self.imports.push(ImportRecord {
+            id: import_id,
+            source: source.to_string(),
+            account_name: history.account_name,
+            imported_at,
+            new_records: new_record_count,
+        });
+
+        new_record_count
+    }
+
+    pub fn account_histories(&self) -> Vec<AccountHistory> {
+        self.accounts
+            .iter()
+            .map(|a| {
+                let mut history = AccountHistory {
+                    account_name: a.account_name.clone(),
+                    account_type: a.account_type,
+                    current_balance_date: a.balance_date(),
+                    current_balance: a.balance.map_or(0.0, |b| b.current_balance),
+                    period: a.period,
+                    records: a.records.iter().map(|r| r.record.clone()).collect(),
+                    positions: a.positions.clone(),
+                };
+                // stores written before records knew their account
+                history.name_records();
+                history
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::model::test_util::{new_record_with_other_side, str_date};
+
+    fn import_time() -> NaiveDateTime {
+        str_date("7.3.2024").and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn new_record(amount: f64, date: &str) -> AccountRecord {
+        AccountRecord {
+            booking_text: "Kartenzahlung".to_string(),
+            account_name: Some("1018793511".to_string()),
+            ..new_record_with_other_side(amount, date, "EDEKA")
+        }
+    }
+
+    fn history(balance_date: &str, records: Vec<AccountRecord>) -> AccountHistory {
+        AccountHistory {
+            account_name: "1018793511".to_string(),
+            account_type: AccountType::DKBAccount,
+            current_balance_date: str_date(balance_date),
+            current_balance: 350.0,
+            period: None,
+            records,
+            positions: vec![],
+        }
+    }
+
+    #[test]
+    fn importing_the_same_statement_twice_is_a_no_op() {
+        let statement = history(
+            "6.3.2024",
+            vec![new_record(-5.0, "5.3.2024"), new_record(-5.0, "5.3.2024")],
+        );
+
+        let mut store = TransactionStore::default();
+        assert_eq!(store.import(statement.clone(), "a.csv", import_time()), 2);
+        assert_eq!(store.import(statement, "a.csv", import_time()), 0);
+
+        assert_eq!(store.imports.len(), 1);
+        assert_eq!(store.accounts[0].records.len(), 2);
+    }
+
+    #[test]
+    fn only_unseen_records_of_an_overlapping_statement_are_added() {
+        let mut store = TransactionStore::default();
+        store.import(
+            history(
+                "4.3.2024",
+                vec![new_record(-5.0, "3.3.2024"), new_record(-20.0, "1.3.2024")],
+            ),
+            "old.csv",
+            import_time(),
+        );
+
+        let added = store.import(
+            history(
+                "6.3.2024",
+                vec![
+                    new_record(-5.0, "5.3.2024"),
+                    new_record(-5.0, "3.3.2024"),
+                    new_record(-5.0, "3.3.2024"),
+                ],
+            ),
+            "new.csv",
+            import_time(),
+        );
+
+        assert_eq!(added, 2);
+
+        let histories = store.account_histories();
+        assert_eq!(histories.len(), 1);
+        assert_eq!(histories[0].current_balance_date, str_date("6.3.2024"));
+        assert_eq!(
+            histories[0].records,
+            vec![
+                new_record(-5.0, "5.3.2024"),
+                new_record(-5.0, "3.3.2024"),
+                new_record(-5.0, "3.3.2024"),
+                new_record(-20.0, "1.3.2024"),
+            ]
+        );
+        assert_eq!(store.imports[1].source, "new.csv");
+        assert_eq!(store.imports[1].new_records, 2);
+    }
+
+    #[test]
+    fn pending_records_are_not_stored_until_they_are_booked() {
+        let pending = AccountRecord {
+            status: BookingStatus::Pending,
+            ..new_record(-5.0, "5.3.2024")
+        };
+
+        let mut store = TransactionStore::default();
+        let added = store.import(
+            history("5.3.2024", vec![pending, new_record(-20.0, "1.3.2024")]),
+            "old.csv",
+            import_time(),
+        );
+        assert_eq!(added, 1);
+
+        let added = store.import(
+            history(
+                "6.3.2024",
+                vec![new_record(-5.0, "5.3.2024"), new_record(-20.0, "1.3.2024")],
+            ),
+            "new.csv",
+            import_time(),
+        );
+        assert_eq!(added, 1);
+
+        assert_eq!(
+            store.account_histories()[0].records,
+            vec![new_record(-5.0, "5.3.2024"), new_record(-20.0, "1.3.2024")]
+        );
+    }
+
+    fn depot_transactions(balance_date: &str) -> AccountHistory {
+        AccountHistory {
+            account_type: AccountType::IngDepot,
+            current_balance: 0.0,
+            records: vec![new_record(-500.0, "1.3.2024")],
+            ..history(balance_date, vec![])
+        }
+    }
+
+    fn depot_holdings(balance_date: &str) -> AccountHistory {
+        AccountHistory {
+            account_type: AccountType::IngDepot,
+            current_balance: 520.0,
+            positions: vec![Position {
+                isin: "IE00B4L5Y983".to_string(),
+                name: "iShares Core MSCI World".to_string(),
+                quantity: 5.0,
+                cost_basis: 500.0,
+                market_value: 520.0,
+            }],
+            ..history(balance_date, vec![])
+        }
+    }
+
+    #[test]
+    fn depot_holdings_imported_after_the_transactions_set_the_balance() {
+        let mut store = TransactionStore::default();
+        store.import(
+            depot_transactions("6.3.2024"),
+            "transactions.csv",
+            import_time(),
+        );
+        assert_eq!(store.accounts[0].balance, None);
+
+        store.import(depot_holdings("5.3.2024"), "holdings.csv", import_time());
+
+        let histories = store.account_histories();
+        assert_eq!(histories[0].current_balance_date, str_date("5.3.2024"));
+        assert_eq!(histories[0].current_balance, 520.0);
+        assert_eq!(histories[0].positions.len(), 1);
+        assert_eq!(histories[0].records.len(), 1);
+    }
+
+    #[test]
+    fn depot_transactions_imported_after_the_holdings_keep_the_balance() {
+        let mut store = TransactionStore::default();
+        store.import(depot_holdings("5.3.2024"), "holdings.csv", import_time());
+
+        store.import(
+            depot_transactions("6.3.2024"),
+            "transactions.csv",
+            import_time(),
+        );
+
+        let histories = store.account_histories();
+        assert_eq!(histories[0].current_balance_date, str_date("5.3.2024"));
+        assert_eq!(histories[0].current_balance, 520.0);
+        assert_eq!(histories[0].positions.len(), 1);
+        assert_eq!(histories[0].records.len(), 1);
+    }
+
+    #[test]
+    fn a_saved_store_can_be_loaded_again() {
+        let mut store = TransactionStore::default();
+        store.import(
+            history("6.3.2024", vec![new_record(-5.0, "5.3.2024")]),
+            "a.csv",
+            import_time(),
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(STORE_FILE_NAME);
+        store.save(&path).unwrap();
+        let loaded = TransactionStore::load(&path).unwrap();
+
+        assert_eq!(loaded, store);
+    }
+
+    #[test]
+    fn loading_a_missing_store_returns_an_empty_store() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(STORE_FILE_NAME);
+
+        let store = TransactionStore::load(&path).unwrap();
+
+        assert_eq!(store, TransactionStore::default());
+    }
+}